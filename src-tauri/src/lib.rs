@@ -1,14 +1,104 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-use std::sync::Mutex;
-use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
-use tauri::{Emitter, State};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
+use tauri_plugin_shell::ShellExt;
 use tauri_plugin_store::StoreExt;
 
 const STORE_FILE: &str = "settings.json";
 const LAST_PROJECT_KEY: &str = "last_project_path";
+const TEMPLATES_DIR: &str = "templates";
+const RECOVERY_DIR: &str = "recovery";
+const THUMBNAILS_DIR: &str = "thumbnails";
+const THUMBNAIL_MAX_AGE_DAYS: u64 = 30;
+const TEMPLATE_MANIFEST: &str = "template.json";
+const PROJECT_CONFIG_DIR: &str = ".retro-ide";
+const PROJECT_MANIFEST: &str = "project.toml";
+const TODO_MARKERS_KEY: &str = "todo_markers";
+const WINDOW_STATE_KEY: &str = "window_state";
+const MAIN_WINDOW: &str = "main";
+const RESTRICT_TO_PROJECT_KEY: &str = "restrict_to_project";
+const THEME_KEY: &str = "theme";
+const DEFAULT_LINE_ENDING_KEY: &str = "default_line_ending";
+const PROJECT_SETTINGS_KEY: &str = "project_settings";
+const FORMATTERS_SETTING: &str = "formatters";
+const EMULATOR_SETTING: &str = "emulator";
+const TREE_EXPANSION_SETTING: &str = "tree_expansion";
+const DISPLAY_NAME_SETTING: &str = "display_name";
+const LINTER_PATTERNS_KEY: &str = "linter_patterns";
+
+// Matches the common "file:line:col: severity: message" tool output
+const DEFAULT_DIAGNOSTIC_PATTERN: &str = r"^(?:.*?:)?(?P<line>\d+):(?:(?P<column>\d+):)?\s*(?:(?i:(?P<severity>error|warning|info|note))\s*:?\s*)?(?P<message>.+)$";
+const RECOVERY_MAX_AGE_KEY: &str = "recovery_max_age_days";
+const DEFAULT_RECOVERY_MAX_AGE_DAYS: u64 = 7;
+// Returned verbatim by git_checkout_branch so the frontend can offer to force
+const DIRTY_WORKTREE_ERROR: &str = "Working tree has uncommitted changes";
+// Prefix of resolve_symlink's error for links whose target is missing
+const BROKEN_SYMLINK_ERROR: &str = "Broken link: ";
+const DEFAULT_THEME: &str = "dark";
+const EDITOR_FONT_KEY: &str = "editor_font";
+const DEFAULT_FONT_FAMILY: &str = "monospace";
+const DEFAULT_FONT_SIZE: u32 = 14;
+const MIN_FONT_SIZE: u32 = 8;
+const MAX_FONT_SIZE: u32 = 72;
+const DEFAULT_TODO_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+const GENERATED_MARKERS_KEY: &str = "generated_markers";
+const DEFAULT_GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "DO NOT EDIT",
+    "auto-generated",
+    "autogenerated",
+];
+// Lines at the top of a file checked for generated markers
+const GENERATED_HEADER_LINES: usize = 5;
+// Average line length above which a .js/.css file is treated as minified
+const MINIFIED_LINE_LENGTH: usize = 500;
+
+// Directories skipped by project-wide walks (in addition to hidden entries)
+const EXCLUDED_DIRS: &[&str] = &["node_modules", "target"];
+
+// Bytes per hex dump row, and the most a single hex read may return
+const HEX_ROW_LEN: usize = 16;
+const MAX_HEX_READ: u64 = 1024 * 1024;
+
+// Chunk size for read_file_with_progress; one read-progress event per chunk
+const READ_CHUNK_SIZE: usize = 256 * 1024;
+
+// Entries per dir-batch event when streaming huge directories
+const DIR_BATCH_SIZE: usize = 500;
+
+// Largest file whose contents are kept in the in-memory search index
+const MAX_INDEXED_FILE_SIZE: u64 = 1024 * 1024;
+const MAX_SEARCH_RESULTS: usize = 5000;
+// Files indexed between index-progress events
+const INDEX_PROGRESS_INTERVAL: usize = 200;
+// Files archived between zip-progress events
+const ZIP_PROGRESS_INTERVAL: usize = 100;
+
+// Largest file highlighted server-side
+const MAX_HIGHLIGHT_SIZE: u64 = 2 * 1024 * 1024;
+
+// Heuristics for detect_entry_points: conventional file stems, and text
+// (matched case-insensitively) that marks the code the CPU starts executing
+const ENTRY_POINT_STEMS: &[&str] = &["main", "start", "boot", "program", "game"];
+const ENTRY_POINT_MARKERS: &[&str] = &["reset vector", "reset:", ".word reset", "fdb reset"];
+
+// Syntaxes for the retro languages, layered over syntect's defaults
+const BUNDLED_SYNTAXES: &[&str] = &[
+    include_str!("../syntaxes/asm6502.sublime-syntax"),
+    include_str!("../syntaxes/asm6809.sublime-syntax"),
+    include_str!("../syntaxes/basic.sublime-syntax"),
+];
+
+// How much of a file is sniffed for null bytes when deciding if it's binary
+const BINARY_SNIFF_LEN: usize = 8192;
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct ProjectState {
@@ -16,19 +106,347 @@ pub struct ProjectState {
     pub name: Option<String>,
 }
 
+// Editor buffer state reported by the frontend for one window
+#[derive(Default, Clone)]
+struct WindowBuffers {
+    active: Option<String>,
+    dirty: HashSet<String>,
+    // On-disk modification time when each open buffer was last loaded or saved
+    loaded: HashMap<String, Option<std::time::SystemTime>>,
+}
+
+// In-memory index of a project's text files for fast repeated searches
+struct FileIndex {
+    root: PathBuf,
+    // Contents are cached only for files up to MAX_INDEXED_FILE_SIZE
+    files: BTreeMap<PathBuf, Option<String>>,
+    // Identifier counts over the cached contents, built on first use
+    identifiers: Option<HashMap<String, usize>>,
+}
+
+// Highlight tokens for a file as of its last modification time
+struct CachedHighlight {
+    modified: std::time::SystemTime,
+    language: String,
+    tokens: Vec<Token>,
+}
+
+// Accumulated latency of one backend command
+#[derive(Default)]
+struct CommandStats {
+    count: u64,
+    total: std::time::Duration,
+    max: std::time::Duration,
+}
+
+// Records the time until it's dropped against a command name
+struct CommandTimer<'a> {
+    state: &'a AppState,
+    command: &'static str,
+    started: std::time::Instant,
+}
+
+impl Drop for CommandTimer<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        let mut timings = self.state.command_timings.lock().unwrap();
+        let stats = timings.entry(self.command).or_default();
+        stats.count += 1;
+        stats.total += elapsed;
+        stats.max = stats.max.max(elapsed);
+    }
+}
+
 struct AppState {
-    project: Mutex<ProjectState>,
+    // Each window has its own project, buffers and watcher, keyed by window label
+    projects: Mutex<HashMap<String, ProjectState>>,
+    buffers: Mutex<HashMap<String, WindowBuffers>>,
+    watchers: Mutex<HashMap<String, notify::RecommendedWatcher>>,
+    git_watchers: Mutex<HashMap<String, notify::RecommendedWatcher>>,
+    // Search indexes, keyed by project root
+    file_indexes: Mutex<HashMap<PathBuf, FileIndex>>,
+    settings_watcher: Mutex<Option<notify::RecommendedWatcher>>,
+    // Built on first use, since loading the syntax definitions isn't free
+    syntax_set: Mutex<Option<syntect::parsing::SyntaxSet>>,
+    highlight_cache: Mutex<HashMap<PathBuf, CachedHighlight>>,
+    // Advisory edit locks: file path -> label of the window holding it
+    file_locks: Mutex<HashMap<String, String>>,
+    // Background processes started by run_task_to_file, keyed by task id
+    tasks: Mutex<HashMap<String, tauri_plugin_shell::process::CommandChild>>,
+    command_timings: Mutex<HashMap<&'static str, CommandStats>>,
+    // Sorted read_directory results for folders inside open projects, kept
+    // fresh by the project watchers
+    dir_cache: Mutex<HashMap<PathBuf, Vec<FileEntry>>>,
+    // Cancellation flags for streaming directory reads, keyed by batch id
+    dir_reads: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    // Failures during setup, held until the frontend is ready to show them
+    startup_errors: Mutex<Vec<String>>,
+    next_window_id: AtomicUsize,
+    next_task_id: AtomicUsize,
+}
+
+impl AppState {
+    fn project(&self, window: &str) -> ProjectState {
+        self.projects
+            .lock()
+            .unwrap()
+            .get(window)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn set_project(&self, window: &str, project: ProjectState) {
+        self.projects
+            .lock()
+            .unwrap()
+            .insert(window.to_string(), project);
+    }
+
+    fn time_command(&self, command: &'static str) -> CommandTimer<'_> {
+        CommandTimer {
+            state: self,
+            command,
+            started: std::time::Instant::now(),
+        }
+    }
+
+    // Only folders a watcher will report changes for are safe to cache
+    fn is_watched(&self, path: &Path) -> bool {
+        self.projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|project| project.path.as_deref())
+            .any(|root| path.starts_with(root))
+    }
+
+    // Drop cached listings of changed paths, their parents, and anything
+    // below them (a removed or renamed folder takes its subtree with it)
+    fn invalidate_dir_cache(&self, paths: &[PathBuf]) {
+        let mut cache = self.dir_cache.lock().unwrap();
+        for path in paths {
+            if let Some(parent) = path.parent() {
+                cache.remove(parent);
+            }
+            cache.retain(|dir, _| !dir.starts_with(path));
+        }
+    }
+
+    fn refresh_index_paths(&self, paths: &[PathBuf]) {
+        let mut indexes = self.file_indexes.lock().unwrap();
+        for index in indexes.values_mut() {
+            for path in paths {
+                index.refresh_path(path);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BackendError {
+    pub message: String,
+}
+
+// Failures in background work have no caller to return to, so they're shown
+// to the user in the affected window instead
+fn report_error(app: &tauri::AppHandle, window: &str, message: String) {
+    let _ = app.emit_to(window, "backend-error", BackendError { message });
+}
+
+// Set a window's project and (re)start its file watcher
+fn activate_project(app: &tauri::AppHandle, window: &str, project: ProjectState) {
+    let state = app.state::<AppState>();
+    state.watchers.lock().unwrap().remove(window);
+    state.git_watchers.lock().unwrap().remove(window);
+
+    // Listings of the previous project stop being kept fresh once unwatched
+    if let Some(previous) = state.project(window).path {
+        state
+            .dir_cache
+            .lock()
+            .unwrap()
+            .retain(|dir, _| !dir.starts_with(&previous));
+    }
+
+    if let Some(path) = &project.path {
+        if let Err(e) = watch_project(app, window, Path::new(path)) {
+            report_error(
+                app,
+                window,
+                format!("Failed to watch project {}: {}", path, e),
+            );
+        }
+        if let Err(e) = watch_git_head(app, window, Path::new(path)) {
+            report_error(
+                app,
+                window,
+                format!("Failed to watch git HEAD for {}: {}", path, e),
+            );
+        }
+    }
+
+    state.set_project(window, project);
+}
+
+// True when `path` is outside `root` or under a hidden/excluded directory
+fn is_within_excluded(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .map(|relative| {
+            relative
+                .components()
+                .any(|c| is_excluded_name(&c.as_os_str().to_string_lossy()))
+        })
+        .unwrap_or(true)
+}
+
+fn watch_project(app: &tauri::AppHandle, window: &str, root: &Path) -> Result<(), String> {
+    use notify::Watcher;
+
+    let handle = app.clone();
+    let label = window.to_string();
+    let watch_root = root.to_path_buf();
+    let config_dir = root.join(PROJECT_CONFIG_DIR);
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, notify::EventKind::Access(_)) {
+            return;
+        }
+
+        // The tree lists excluded folders too, so their listings are
+        // invalidated before filtering
+        handle
+            .state::<AppState>()
+            .invalidate_dir_cache(&event.paths);
+
+        // .retro-ide is hidden from the tree but its manifest drives the build
+        // tools, so changes there get their own event
+        if event.paths.iter().any(|p| p.starts_with(&config_dir)) {
+            let _ = handle.emit_to(label.as_str(), "project-config-changed", ());
+        }
+
+        let paths: Vec<PathBuf> = event
+            .paths
+            .into_iter()
+            .filter(|p| !is_within_excluded(&watch_root, p))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        handle.state::<AppState>().refresh_index_paths(&paths);
+
+        let changed: Vec<String> = paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let _ = handle.emit_to(label.as_str(), "fs-changed", changed);
+    })
+    .map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(root, notify::RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    app.state::<AppState>()
+        .watchers
+        .lock()
+        .unwrap()
+        .insert(window.to_string(), watcher);
+
+    Ok(())
+}
+
+// The repository found by discover_repo may sit above the project root, out of
+// reach of the project watcher, so HEAD gets its own watcher. The git directory
+// is watched rather than the file because git replaces HEAD by renaming a lock
+// file over it.
+fn watch_git_head(app: &tauri::AppHandle, window: &str, root: &Path) -> Result<(), String> {
+    use notify::Watcher;
+
+    let Some(repo) = discover_repo(root)? else {
+        return Ok(());
+    };
+    let git_dir = repo.path().to_path_buf();
+
+    let handle = app.clone();
+    let label = window.to_string();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, notify::EventKind::Access(_)) {
+            return;
+        }
+
+        if event
+            .paths
+            .iter()
+            .any(|p| p.file_name().is_some_and(|name| name == "HEAD"))
+        {
+            let _ = handle.emit_to(label.as_str(), "git-head-changed", ());
+        }
+    })
+    .map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(&git_dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    app.state::<AppState>()
+        .git_watchers
+        .lock()
+        .unwrap()
+        .insert(window.to_string(), watcher);
+
+    Ok(())
+}
+
+fn project_from_path(path_str: &str) -> ProjectState {
+    let name = Path::new(path_str)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path_str.to_string());
+
+    ProjectState {
+        path: Some(path_str.to_string()),
+        name: Some(name),
+    }
+}
+
+// The project for a folder, using its display name override when one is set
+fn project_with_display_name(app: &tauri::AppHandle, path_str: &str) -> ProjectState {
+    let mut project = project_from_path(path_str);
+    if let Some(name) = read_project_setting(app, path_str, DISPLAY_NAME_SETTING)
+        .and_then(|v| v.as_str().map(String::from))
+    {
+        project.name = Some(name);
+    }
+    project
+}
+
+#[tauri::command]
+fn get_current_project(window: tauri::Window, state: State<AppState>) -> ProjectState {
+    state.project(window.label())
 }
 
 #[tauri::command]
-fn get_current_project(state: State<AppState>) -> ProjectState {
-    state.project.lock().unwrap().clone()
+fn get_project_name(window: tauri::Window, state: State<AppState>) -> Option<String> {
+    state
+        .projects
+        .lock()
+        .unwrap()
+        .get(window.label())
+        .and_then(|project| project.name.clone())
 }
 
 #[tauri::command]
 async fn open_project_dialog(
     app: tauri::AppHandle,
-    state: State<'_, AppState>,
+    window: tauri::Window,
 ) -> Result<Option<ProjectState>, String> {
     let folder = app
         .dialog()
@@ -39,15 +457,7 @@ async fn open_project_dialog(
     match folder {
         Some(path) => {
             let path_str = path.to_string();
-            let name = std::path::Path::new(&path_str)
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| path_str.clone());
-
-            let project = ProjectState {
-                path: Some(path_str.clone()),
-                name: Some(name),
-            };
+            let project = project_with_display_name(&app, &path_str);
 
             // Save to persistent store
             if let Ok(store) = app.store(STORE_FILE) {
@@ -56,7 +466,7 @@ async fn open_project_dialog(
             }
 
             // Update app state
-            *state.project.lock().unwrap() = project.clone();
+            activate_project(&app, window.label(), project.clone());
 
             Ok(Some(project))
         }
@@ -67,24 +477,23 @@ async fn open_project_dialog(
 #[tauri::command]
 async fn load_last_project(
     app: tauri::AppHandle,
+    window: tauri::Window,
     state: State<'_, AppState>,
 ) -> Result<Option<ProjectState>, String> {
+    // Windows opened for a specific project already have one assigned
+    let assigned = state.project(window.label());
+    if assigned.path.is_some() {
+        return Ok(Some(assigned));
+    }
+
     if let Ok(store) = app.store(STORE_FILE) {
         if let Some(value) = store.get(LAST_PROJECT_KEY) {
             if let Some(path_str) = value.as_str() {
                 // Verify the path still exists
                 if std::path::Path::new(path_str).exists() {
-                    let name = std::path::Path::new(path_str)
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| path_str.to_string());
+                    let project = project_with_display_name(&app, path_str);
 
-                    let project = ProjectState {
-                        path: Some(path_str.to_string()),
-                        name: Some(name),
-                    };
-
-                    *state.project.lock().unwrap() = project.clone();
+                    activate_project(&app, window.label(), project.clone());
                     return Ok(Some(project));
                 }
             }
@@ -94,28 +503,106 @@ async fn load_last_project(
 }
 
 #[tauri::command]
-async fn close_project(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<(), String> {
-    // Clear persistent store
+async fn close_project(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let closing = state.project(window.label());
+
+    // Clear persistent store, unless another window's project is the remembered one
     if let Ok(store) = app.store(STORE_FILE) {
-        let _ = store.delete(LAST_PROJECT_KEY);
-        let _ = store.save();
+        let remembered = store.get(LAST_PROJECT_KEY);
+        if remembered.is_none()
+            || remembered.as_ref().and_then(|v| v.as_str()) == closing.path.as_deref()
+        {
+            let _ = store.delete(LAST_PROJECT_KEY);
+            let _ = store.save();
+        }
     }
 
     // Clear app state
-    *state.project.lock().unwrap() = ProjectState::default();
+    activate_project(&app, window.label(), ProjectState::default());
+    Ok(())
+}
+
+#[tauri::command]
+async fn open_project_in_new_window(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    if !Path::new(&path).is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let project = project_with_display_name(&app, &path);
+    let id = state.next_window_id.fetch_add(1, Ordering::Relaxed);
+    let label = format!("project-{}", id);
+
+    // Assign the project before the window loads so its load_last_project picks it up
+    activate_project(&app, &label, project.clone());
+
+    let result =
+        tauri::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App("index.html".into()))
+            .title(format!(
+                "Retro IDE — {}",
+                project.name.as_deref().unwrap_or_default()
+            ))
+            .inner_size(1024.0, 768.0)
+            .min_inner_size(800.0, 600.0)
+            .build();
+
+    if let Err(e) = result {
+        state.projects.lock().unwrap().remove(&label);
+        state.watchers.lock().unwrap().remove(&label);
+        state.git_watchers.lock().unwrap().remove(&label);
+        return Err(e.to_string());
+    }
+
     Ok(())
 }
 
+// Menu events go to the focused window so each window acts on its own project
+fn emit_to_focused(app: &tauri::AppHandle, event: &str) {
+    let focused = app
+        .webview_windows()
+        .into_values()
+        .find(|window| window.is_focused().unwrap_or(false));
+
+    match focused {
+        Some(window) => {
+            let _ = app.emit_to(window.label(), event, ());
+        }
+        None => {
+            let _ = app.emit(event, ());
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    pub is_symlink: bool,
     pub children: Option<Vec<FileEntry>>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirectoryListing {
+    pub entries: Vec<FileEntry>,
+    pub truncated: bool,
+    pub total: usize,
+}
+
 #[tauri::command]
-async fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
+async fn read_directory(
+    state: State<'_, AppState>,
+    path: String,
+    limit: Option<usize>,
+) -> Result<DirectoryListing, String> {
+    let _timer = state.time_command("read_directory");
     let path = Path::new(&path);
 
     if !path.exists() {
@@ -126,6 +613,42 @@ async fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
         return Err("Path is not a directory".to_string());
     }
 
+    let cached = state.dir_cache.lock().unwrap().get(path).cloned();
+    let mut entries = match cached {
+        Some(entries) => entries,
+        None => {
+            let entries = list_directory(path)?;
+            if state.is_watched(path) {
+                state
+                    .dir_cache
+                    .lock()
+                    .unwrap()
+                    .insert(path.to_path_buf(), entries.clone());
+            }
+            entries
+        }
+    };
+
+    // Truncate after sorting so the first entries are stable between reads
+    let total = entries.len();
+    let truncated = limit.is_some_and(|limit| total > limit);
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+
+    Ok(DirectoryListing {
+        entries,
+        truncated,
+        total,
+    })
+}
+
+#[tauri::command]
+fn clear_directory_cache(state: State<AppState>) {
+    state.dir_cache.lock().unwrap().clear();
+}
+
+fn list_directory(path: &Path) -> Result<Vec<FileEntry>, String> {
     let mut entries: Vec<FileEntry> = Vec::new();
 
     let read_dir = fs::read_dir(path).map_err(|e| e.to_string())?;
@@ -146,24 +669,71 @@ async fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
             name,
             path: entry_path.to_string_lossy().to_string(),
             is_dir,
+            is_symlink: entry.file_type().is_ok_and(|t| t.is_symlink()),
             children: None, // Children are loaded on demand
         });
     }
 
-    // Sort: directories first, then files, both alphabetically
+    sort_entries(&mut entries);
+
+    Ok(entries)
+}
+
+// Sort: directories first, then files, both alphabetically
+fn sort_entries(entries: &mut [FileEntry]) {
     entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
         (true, false) => std::cmp::Ordering::Less,
         (false, true) => std::cmp::Ordering::Greater,
         _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
     });
-
-    Ok(entries)
 }
 
 #[tauri::command]
-async fn read_file_contents(path: String) -> Result<String, String> {
+async fn list_sibling_dirs(path: String) -> Result<Vec<FileEntry>, String> {
     let path = Path::new(&path);
 
+    if !path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Path has no parent directory".to_string())?;
+
+    let mut entries: Vec<FileEntry> = Vec::new();
+
+    for entry in fs::read_dir(parent).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name.starts_with('.') || !entry_path.is_dir() {
+            continue;
+        }
+
+        entries.push(FileEntry {
+            name,
+            path: entry_path.to_string_lossy().to_string(),
+            is_dir: true,
+            is_symlink: entry.file_type().is_ok_and(|t| t.is_symlink()),
+            children: None,
+        });
+    }
+
+    sort_entries(&mut entries);
+
+    Ok(entries)
+}
+
+const UTF8_BOM: &str = "\u{FEFF}";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TextFileContents {
+    pub contents: String,
+    pub has_bom: bool,
+}
+
+fn read_text_file(path: &Path) -> Result<TextFileContents, String> {
     if !path.exists() {
         return Err("File does not exist".to_string());
     }
@@ -172,12 +742,77 @@ async fn read_file_contents(path: String) -> Result<String, String> {
         return Err("Path is not a file".to_string());
     }
 
-    fs::read_to_string(path).map_err(|e| e.to_string())
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    // A UTF-8 BOM would otherwise show up as a stray character in the editor
+    Ok(match contents.strip_prefix(UTF8_BOM) {
+        Some(stripped) => TextFileContents {
+            contents: stripped.to_string(),
+            has_bom: true,
+        },
+        None => TextFileContents {
+            contents,
+            has_bom: false,
+        },
+    })
+}
+
+fn disk_modified(path: &Path) -> Option<std::time::SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn record_buffer_loaded(state: &AppState, window: &str, path: &str) {
+    let mut buffers = state.buffers.lock().unwrap();
+    buffers
+        .entry(window.to_string())
+        .or_default()
+        .loaded
+        .insert(path.to_string(), disk_modified(Path::new(path)));
 }
 
 #[tauri::command]
-async fn write_file_contents(path: String, contents: String) -> Result<(), String> {
-    let path = Path::new(&path);
+async fn read_file_contents(
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<String, String> {
+    let _timer = state.time_command("read_file_contents");
+    let file = read_text_file(Path::new(&path))?;
+    record_buffer_loaded(&state, window.label(), &path);
+    Ok(file.contents)
+}
+
+#[tauri::command]
+async fn read_file_with_bom(
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<TextFileContents, String> {
+    let file = read_text_file(Path::new(&path))?;
+    record_buffer_loaded(&state, window.label(), &path);
+    Ok(file)
+}
+
+// fs::write on a folder fails with an opaque OS error; say what's wrong instead
+fn check_not_directory(path: &Path) -> Result<(), String> {
+    if path.is_dir() {
+        return Err("Cannot write: path is a directory".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn write_file_contents(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+    contents: String,
+    write_bom: Option<bool>,
+) -> Result<(), String> {
+    let file_path = path;
+    let path = Path::new(&file_path);
+    check_not_directory(path)?;
 
     // Create parent directories if they don't exist
     if let Some(parent) = path.parent() {
@@ -186,168 +821,6454 @@ async fn write_file_contents(path: String, contents: String) -> Result<(), Strin
         }
     }
 
-    fs::write(path, contents).map_err(|e| e.to_string())
+    if write_bom.unwrap_or(false) && !contents.starts_with(UTF8_BOM) {
+        fs::write(path, format!("{}{}", UTF8_BOM, contents)).map_err(|e| e.to_string())?;
+    } else {
+        fs::write(path, contents).map_err(|e| e.to_string())?;
+    }
+
+    record_buffer_loaded(&state, window.label(), &file_path);
+
+    // The buffer is safely on disk, so its crash-recovery copy is obsolete
+    if let Ok(dir) = recovery_dir(&app) {
+        let _ = fs::remove_file(dir.join(recovery_file_name(&file_path)));
+    }
+
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct FileData {
-    pub data: String,
-    pub mime_type: String,
+fn lookup_encoding(label: &str) -> Result<&'static encoding_rs::Encoding, String> {
+    encoding_rs::Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))
 }
 
 #[tauri::command]
-async fn read_file_binary(path: String) -> Result<FileData, String> {
-    use base64::{engine::general_purpose::STANDARD, Engine};
-
-    let file_path = Path::new(&path);
+async fn read_file_with_encoding(path: String, encoding: String) -> Result<String, String> {
+    let encoding = lookup_encoding(&encoding)?;
+    let path = Path::new(&path);
 
-    if !file_path.exists() {
+    if !path.exists() {
         return Err("File does not exist".to_string());
     }
 
-    if !file_path.is_file() {
+    if !path.is_file() {
         return Err("Path is not a file".to_string());
     }
 
-    let bytes = fs::read(file_path).map_err(|e| e.to_string())?;
-    let data = STANDARD.encode(&bytes);
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let (contents, _) = encoding.decode_with_bom_removal(&bytes);
 
-    // Determine MIME type from extension
-    let mime_type = match file_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .map(|e| e.to_lowercase())
-        .as_deref()
-    {
-        Some("png") => "image/png",
-        Some("jpg") | Some("jpeg") => "image/jpeg",
-        Some("gif") => "image/gif",
-        Some("webp") => "image/webp",
-        Some("bmp") => "image/bmp",
-        Some("ico") => "image/x-icon",
-        Some("svg") => "image/svg+xml",
-        _ => "application/octet-stream",
+    Ok(contents.into_owned())
+}
+
+#[tauri::command]
+async fn write_file_with_encoding(
+    path: String,
+    contents: String,
+    encoding: String,
+) -> Result<(), String> {
+    let encoding = lookup_encoding(&encoding)?;
+    let path = Path::new(&path);
+    check_not_directory(path)?;
+
+    // encoding_rs only decodes UTF-16 and a few others; encoding falls back to UTF-8
+    if encoding.output_encoding() != encoding {
+        return Err(format!("Cannot write files as {}", encoding.name()));
     }
-    .to_string();
 
-    Ok(FileData { data, mime_type })
-}
+    let (bytes, _, had_errors) = encoding.encode(&contents);
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_store::Builder::default().build())
-        .manage(AppState {
-            project: Mutex::new(ProjectState::default()),
-        })
-        .setup(|app| {
-            // App menu items (macOS "Retro IDE" menu)
-            let about = MenuItemBuilder::with_id("about", "About Retro IDE").build(app)?;
-
-            // File menu items
-            let open_project = MenuItemBuilder::with_id("open_project", "Open Project...")
-                .accelerator("CmdOrCtrl+O")
-                .build(app)?;
-            let close_project =
-                MenuItemBuilder::with_id("close_project", "Close Project").build(app)?;
-            let save_file = MenuItemBuilder::with_id("save_file", "Save")
-                .accelerator("CmdOrCtrl+S")
-                .build(app)?;
-
-            // Edit menu items
-            let undo = MenuItemBuilder::with_id("undo", "Undo")
-                .accelerator("CmdOrCtrl+Z")
-                .build(app)?;
-            let redo = MenuItemBuilder::with_id("redo", "Redo")
-                .accelerator("CmdOrCtrl+Shift+Z")
-                .build(app)?;
-            let cut = MenuItemBuilder::with_id("cut", "Cut")
-                .accelerator("CmdOrCtrl+X")
-                .build(app)?;
-            let copy = MenuItemBuilder::with_id("copy", "Copy")
-                .accelerator("CmdOrCtrl+C")
-                .build(app)?;
-            let paste = MenuItemBuilder::with_id("paste", "Paste")
-                .accelerator("CmdOrCtrl+V")
-                .build(app)?;
-            let select_all = MenuItemBuilder::with_id("select_all", "Select All")
-                .accelerator("CmdOrCtrl+A")
-                .build(app)?;
-
-            // Build App submenu (macOS application menu)
-            let app_menu = SubmenuBuilder::new(app, "Retro IDE")
-                .item(&about)
-                .separator()
-                .quit()
-                .build()?;
-
-            // Build File submenu
-            let file_menu = SubmenuBuilder::new(app, "File")
-                .item(&open_project)
-                .item(&close_project)
-                .separator()
-                .item(&save_file)
-                .build()?;
-
-            // Build Edit submenu
-            let edit_menu = SubmenuBuilder::new(app, "Edit")
-                .item(&undo)
-                .item(&redo)
-                .separator()
-                .item(&cut)
-                .item(&copy)
-                .item(&paste)
-                .separator()
-                .item(&select_all)
-                .build()?;
-
-            // Build the full menu
-            let menu = MenuBuilder::new(app)
-                .item(&app_menu)
-                .item(&file_menu)
-                .item(&edit_menu)
-                .build()?;
-
-            // Set the menu
-            app.set_menu(menu)?;
+    if had_errors {
+        let mut unmappable: Vec<char> = Vec::new();
+        for c in contents.chars() {
+            let mut buf = [0u8; 4];
+            let (_, _, failed) = encoding.encode(c.encode_utf8(&mut buf));
+            if failed && !unmappable.contains(&c) {
+                unmappable.push(c);
+            }
+        }
 
-            Ok(())
+        let listed: Vec<String> = unmappable.iter().map(|c| format!("'{}'", c)).collect();
+        return Err(format!(
+            "Characters not representable in {}: {}",
+            encoding.name(),
+            listed.join(", ")
+        ));
+    }
+
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+    }
+
+    fs::write(path, bytes).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HexRow {
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+    pub ascii: String,
+}
+
+#[tauri::command]
+async fn read_file_hex(path: String, offset: u64, length: u64) -> Result<Vec<HexRow>, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    if !path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+
+    // Reading past EOF simply yields fewer (or no) rows
+    let mut buf = Vec::new();
+    file.take(length.min(MAX_HEX_READ))
+        .read_to_end(&mut buf)
+        .map_err(|e| e.to_string())?;
+
+    let rows = buf
+        .chunks(HEX_ROW_LEN)
+        .enumerate()
+        .map(|(idx, chunk)| HexRow {
+            offset: offset + (idx * HEX_ROW_LEN) as u64,
+            bytes: chunk.to_vec(),
+            ascii: chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+// Decode patch bytes given as "hex" (whitespace allowed, e.g. "A9 00 8D") or "base64"
+fn decode_patch_bytes(input: &str, encoding: &str) -> Result<Vec<u8>, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let compact: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+
+    if compact.is_empty() {
+        return Err("No bytes given".to_string());
+    }
+
+    match encoding {
+        "hex" => {
+            if compact.len() % 2 != 0 || !compact.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err("Bytes are not valid hex".to_string());
+            }
+            (0..compact.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&compact[i..i + 2], 16).map_err(|e| e.to_string()))
+                .collect()
+        }
+        "base64" => STANDARD
+            .decode(&compact)
+            .map_err(|_| "Bytes are not valid base64".to_string()),
+        _ => Err(format!("Unknown byte encoding: {}", encoding)),
+    }
+}
+
+#[tauri::command]
+async fn patch_file_bytes(
+    path: String,
+    offset: u64,
+    bytes: String,
+    encoding: String,
+    extend: bool,
+) -> Result<(), String> {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let path = Path::new(&path);
+
+    if !path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    if !path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let patch = decode_patch_bytes(&bytes, &encoding)?;
+    let file_len = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    let end = offset
+        .checked_add(patch.len() as u64)
+        .ok_or_else(|| "Patch offset is out of range".to_string())?;
+
+    if !extend && end > file_len {
+        return Err(format!(
+            "Patch would write past end of file ({} bytes)",
+            file_len
+        ));
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+    file.write_all(&patch).map_err(|e| e.to_string())?;
+    file.flush().map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileData {
+    pub data: String,
+    pub mime_type: String,
+}
+
+#[tauri::command]
+async fn read_file_binary(path: String) -> Result<FileData, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let file_path = Path::new(&path);
+
+    if !file_path.exists() {
+        return Err("File does not exist".to_string());
+    }
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let bytes = fs::read(file_path).map_err(|e| e.to_string())?;
+    let data = STANDARD.encode(&bytes);
+
+    // Determine MIME type from extension
+    let mime_type = match file_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("ico") => "image/x-icon",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+    .to_string();
+
+    Ok(FileData { data, mime_type })
+}
+
+#[derive(Deserialize, Default)]
+struct TemplateManifest {
+    name: Option<String>,
+    description: Option<String>,
+    platform: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TemplateInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub platform: Option<String>,
+    pub path: String,
+}
+
+fn templates_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(TEMPLATES_DIR))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_disk_templates(app: tauri::AppHandle) -> Result<Vec<TemplateInfo>, String> {
+    let dir = templates_dir(&app)?;
+
+    // No templates directory simply means no user templates yet
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates: Vec<TemplateInfo> = Vec::new();
+
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let template_path = entry.path();
+        let manifest_path = template_path.join(TEMPLATE_MANIFEST);
+
+        // Only folders carrying a manifest are considered templates
+        if !template_path.is_dir() || !manifest_path.is_file() {
+            continue;
+        }
+
+        let manifest: TemplateManifest = fs::read_to_string(&manifest_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        let id = entry.file_name().to_string_lossy().to_string();
+
+        templates.push(TemplateInfo {
+            name: manifest.name.unwrap_or_else(|| id.clone()),
+            description: manifest.description.unwrap_or_default(),
+            platform: manifest.platform,
+            path: template_path.to_string_lossy().to_string(),
+            id,
+        });
+    }
+
+    templates.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+    Ok(templates)
+}
+
+fn copy_dir_recursive(source: &Path, dest: &Path) -> Result<(), String> {
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+
+    for entry in fs::read_dir(source).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path();
+        let target = dest.join(entry.file_name());
+
+        if entry_path.is_dir() {
+            copy_dir_recursive(&entry_path, &target)?;
+        } else {
+            fs::copy(&entry_path, &target).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn create_project(
+    app: tauri::AppHandle,
+    template_id: String,
+    dest: String,
+) -> Result<String, String> {
+    check_filename(&template_id)?;
+    let template_path = templates_dir(&app)?.join(&template_id);
+    let dest_path = Path::new(&dest);
+
+    if !template_path.join(TEMPLATE_MANIFEST).is_file() {
+        return Err(format!("Template not found: {}", template_id));
+    }
+
+    if dest_path.exists()
+        && fs::read_dir(dest_path)
+            .map_err(|e| e.to_string())?
+            .next()
+            .is_some()
+    {
+        return Err("Destination is not empty".to_string());
+    }
+
+    copy_dir_recursive(&template_path, dest_path)?;
+
+    // The manifest describes the template, not the project created from it
+    let _ = fs::remove_file(dest_path.join(TEMPLATE_MANIFEST));
+
+    apply_default_line_ending_to_tree(&app, dest_path)?;
+
+    Ok(dest_path.to_string_lossy().to_string())
+}
+
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn check_filename(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Name cannot be empty".to_string());
+    }
+
+    if name == "." || name == ".." {
+        return Err("Name cannot be '.' or '..'".to_string());
+    }
+
+    if name.contains('/') || name.contains('\\') {
+        return Err("Name cannot contain path separators".to_string());
+    }
+
+    if name.contains('\0') {
+        return Err("Name cannot contain null characters".to_string());
+    }
+
+    if cfg!(windows) {
+        if let Some(c) = name
+            .chars()
+            .find(|c| matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') || c.is_control())
+        {
+            return Err(format!("Name cannot contain '{}'", c));
+        }
+    }
+
+    // Windows strips these silently, so they never survive a round trip
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err("Name cannot end with a dot or a space".to_string());
+    }
+
+    // Reserved device names apply with any extension (e.g. "con.txt")
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_WINDOWS_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(format!("'{}' is a reserved name on Windows", stem));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+fn validate_filename(name: String) -> Result<(), String> {
+    check_filename(&name)
+}
+
+fn is_case_only_rename(from: &Path, to: &Path) -> bool {
+    from != to && from.to_string_lossy().to_lowercase() == to.to_string_lossy().to_lowercase()
+}
+
+// Whether two paths name the same directory entry, e.g. differently-cased
+// spellings on a case-insensitive volume
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+// A case-only rename may hop through a temporary name, unless the new casing
+// is a different file that would be overwritten (case-sensitive volumes)
+fn is_safe_case_only_rename(from: &Path, to: &Path) -> bool {
+    is_case_only_rename(from, to) && (fs::symlink_metadata(to).is_err() || is_same_file(from, to))
+}
+
+fn rename_via_temp(from: &Path, to: &Path) -> Result<(), String> {
+    let file_name = from
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| "Path has no file name".to_string())?;
+
+    let mut attempt = 0;
+    let temp = loop {
+        let candidate = from.with_file_name(format!(".{}.rename-{}", file_name, attempt));
+        if !candidate.exists() {
+            break candidate;
+        }
+        attempt += 1;
+    };
+
+    fs::rename(from, &temp).map_err(|e| e.to_string())?;
+
+    if let Err(e) = fs::rename(&temp, to) {
+        // Put the original back so a failed rename never loses the file
+        let _ = fs::rename(&temp, from);
+        return Err(e.to_string());
+    }
+
+    Ok(())
+}
+
+// "Operation not supported" codes that std doesn't map to ErrorKind::Unsupported
+#[cfg(target_os = "linux")]
+const EOPNOTSUPP: Option<i32> = Some(95);
+#[cfg(target_os = "macos")]
+const EOPNOTSUPP: Option<i32> = Some(45); // ENOTSUP
+#[cfg(windows)]
+const EOPNOTSUPP: Option<i32> = Some(50); // ERROR_NOT_SUPPORTED
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+const EOPNOTSUPP: Option<i32> = None;
+
+// Errors from fs::rename that a copy can work around: moving across devices,
+// and network filesystems (SMB/NFS) that don't support rename for the entry
+fn needs_copy_fallback(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::CrossesDevices | std::io::ErrorKind::Unsupported
+    ) || (EOPNOTSUPP.is_some() && e.raw_os_error() == EOPNOTSUPP)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenameFallback {
+    pub from: String,
+    pub to: String,
+}
+
+// Move by copying with `copy`, then deleting the source. The source is only
+// removed once the copy has fully succeeded.
+fn copy_then_delete(
+    from: &Path,
+    to: &Path,
+    copy: impl FnOnce(&Path, &Path) -> Result<(), String>,
+) -> Result<(), String> {
+    if let Err(copy_error) = copy(from, to) {
+        // Don't leave a partial copy behind
+        let _ = if to.is_dir() {
+            fs::remove_dir_all(to)
+        } else {
+            fs::remove_file(to)
+        };
+        return Err(copy_error);
+    }
+
+    if from.is_dir() {
+        fs::remove_dir_all(from).map_err(|e| e.to_string())
+    } else {
+        fs::remove_file(from).map_err(|e| e.to_string())
+    }
+}
+
+// Rename, falling back to copy-then-delete where rename can't work
+fn rename_with_fallback(window: &tauri::Window, from: &Path, to: &Path) -> Result<(), String> {
+    let e = match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if needs_copy_fallback(&e) => e,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let _ = window.emit_to(
+        window.label(),
+        "rename-slow-fallback",
+        RenameFallback {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+        },
+    );
+
+    copy_then_delete(from, to, copy_entry)
+        .map_err(|copy_error| format!("{} (copy fallback failed: {})", e, copy_error))
+}
+
+#[tauri::command]
+async fn rename_path(
+    window: tauri::Window,
+    old_path: String,
+    new_path: String,
+) -> Result<String, String> {
+    let from = Path::new(&old_path);
+    let to = Path::new(&new_path);
+
+    if !from.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    if let Some(name) = to.file_name() {
+        check_filename(&name.to_string_lossy())?;
+    }
+
+    if from == to {
+        return Ok(new_path);
+    }
+
+    // On case-insensitive volumes a case-only rename is silently a no-op,
+    // so hop through a temporary name to make the new casing stick
+    if is_safe_case_only_rename(from, to) {
+        rename_via_temp(from, to)?;
+        return Ok(new_path);
+    }
+
+    if to.exists() {
+        return Err("A file or folder with that name already exists".to_string());
+    }
+
+    rename_with_fallback(&window, from, to)?;
+
+    Ok(new_path)
+}
+
+// Line-comment prefixes per language id (matching the frontend's LanguageMode ids).
+// The first prefix is the one inserted; all are recognized when uncommenting.
+fn line_comment_prefixes(language: &str) -> Option<&'static [&'static str]> {
+    match language {
+        "asm" | "asm6502" | "asm6809" => Some(&[";"]),
+        "basic" | "basic-cbm" => Some(&["REM", "'"]),
+        "basic-ecb" => Some(&["'", "REM"]),
+        "c" | "cpp" => Some(&["//"]),
+        "python" | "shell" => Some(&["#"]),
+        _ => None,
+    }
+}
+
+fn is_basic_language(language: &str) -> bool {
+    language == "basic" || language.starts_with("basic-")
+}
+
+// Split a line into the part a comment goes after (indentation, plus the
+// line number for BASIC) and the code the comment applies to
+fn split_comment_target(line: &str, basic: bool) -> (&str, &str) {
+    let mut split = line.len() - line.trim_start().len();
+
+    if basic {
+        let rest = &line[split..];
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits > 0 {
+            let after = &rest[digits..];
+            split += digits + (after.len() - after.trim_start().len());
+        }
+    }
+
+    line.split_at(split)
+}
+
+// Keyword prefixes such as REM must end at a word boundary, so that a BASIC
+// line like `REMAINING = 5` isn't taken for a comment
+fn find_comment_prefix(body: &str, prefixes: &[&'static str]) -> Option<&'static str> {
+    prefixes.iter().copied().find(|prefix| {
+        let keyword = prefix.ends_with(is_identifier_char);
+        body.get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+            && !(keyword && body[prefix.len()..].starts_with(is_identifier_char))
+    })
+}
+
+#[tauri::command]
+fn toggle_line_comment(language: String, lines: Vec<String>) -> Result<Vec<String>, String> {
+    let prefixes = line_comment_prefixes(&language)
+        .ok_or_else(|| format!("No line comment syntax for language: {}", language))?;
+    let basic = is_basic_language(&language);
+
+    // Blank lines don't count towards (or get) a comment
+    let all_commented = lines
+        .iter()
+        .filter(|line| !split_comment_target(line, basic).1.is_empty())
+        .all(|line| find_comment_prefix(split_comment_target(line, basic).1, prefixes).is_some());
+
+    let toggled = lines
+        .iter()
+        .map(|line| {
+            let (head, body) = split_comment_target(line, basic);
+            if body.is_empty() {
+                return line.clone();
+            }
+
+            if all_commented {
+                let prefix = find_comment_prefix(body, prefixes).unwrap_or_default();
+                let rest = &body[prefix.len()..];
+                format!("{}{}", head, rest.strip_prefix(' ').unwrap_or(rest))
+            } else {
+                format!("{}{} {}", head, prefixes[0], body)
+            }
+        })
+        .collect();
+
+    Ok(toggled)
+}
+
+const ASM_BLOCK_OPEN: &[&str] = &[
+    ".macro", ".mac", ".proc", ".scope", ".struct", ".union", ".enum", ".if", ".ifdef", ".ifndef",
+    ".repeat", ".rept",
+];
+const ASM_BLOCK_CLOSE: &[&str] = &[
+    ".endmacro",
+    ".endmac",
+    ".endm",
+    ".endproc",
+    ".endscope",
+    ".endstruct",
+    ".endunion",
+    ".endenum",
+    ".endif",
+    ".endrepeat",
+    ".endrep",
+    ".endr",
+];
+const ASM_BLOCK_MIDDLE: &[&str] = &[".else", ".elseif"];
+
+// Net brace depth change for a C-like line, ignoring strings, chars and comments.
+// Returns the change and whether the line leaves a block comment open.
+fn c_brace_delta(line: &str, mut in_block_comment: bool) -> (i32, bool) {
+    let mut delta = 0;
+    let mut chars = line.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if in_block_comment {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                in_block_comment = false;
+            }
+            continue;
+        }
+
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                in_block_comment = true;
+            }
+            '{' => delta += 1,
+            '}' => delta -= 1,
+            _ => {}
+        }
+    }
+
+    (delta, in_block_comment)
+}
+
+fn reindent_c_like(lines: &[&str], unit: &str) -> Vec<String> {
+    let mut depth: i32 = 0;
+    let mut in_block_comment = false;
+
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let was_in_comment = in_block_comment;
+            let (delta, still_in_comment) = c_brace_delta(trimmed, in_block_comment);
+            in_block_comment = still_in_comment;
+
+            // Leave block comment bodies and blank lines exactly as they were
+            if was_in_comment {
+                depth = (depth + delta).max(0);
+                return line.to_string();
+            }
+            if trimmed.is_empty() {
+                return String::new();
+            }
+
+            let level = if trimmed.starts_with('#') {
+                0
+            } else if trimmed.starts_with('}') {
+                depth - 1
+            } else {
+                depth
+            };
+            depth = (depth + delta).max(0);
+
+            format!("{}{}", unit.repeat(level.max(0) as usize), trimmed)
+        })
+        .collect()
+}
+
+fn reindent_assembly(lines: &[&str], unit: &str) -> Vec<String> {
+    let mut depth: usize = 0;
+
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() {
+                return String::new();
+            }
+
+            let first = trimmed
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+
+            let level = if ASM_BLOCK_CLOSE.contains(&first.as_str()) {
+                depth = depth.saturating_sub(1);
+                depth + 1
+            } else if ASM_BLOCK_MIDDLE.contains(&first.as_str()) {
+                depth
+            } else if ASM_BLOCK_OPEN.contains(&first.as_str()) {
+                depth += 1;
+                depth
+            } else if first.ends_with(':') || !line.starts_with(char::is_whitespace) {
+                // Labels and anything already in column 0 (labels without a
+                // colon, column-0 comments) stay flush left
+                0
+            } else {
+                depth + 1
+            };
+
+            format!("{}{}", unit.repeat(level), trimmed)
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn reindent(
+    language: String,
+    text: String,
+    indent_style: String,
+    indent_size: usize,
+) -> Result<String, String> {
+    let unit = match indent_style.as_str() {
+        "tabs" => "\t".to_string(),
+        "spaces" if (1..=16).contains(&indent_size) => " ".repeat(indent_size),
+        "spaces" => return Err("Indent size must be between 1 and 16".to_string()),
+        _ => return Err(format!("Unknown indent style: {}", indent_style)),
+    };
+
+    let crlf = text.contains("\r\n");
+    let lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    let reindented = match language.as_str() {
+        "c" | "cpp" => reindent_c_like(&lines, &unit),
+        "asm" | "asm6502" | "asm6809" => reindent_assembly(&lines, &unit),
+        _ => {
+            return Err(format!(
+                "Reindent is not supported for language: {}",
+                language
+            ))
+        }
+    };
+
+    Ok(reindented.join(if crlf { "\r\n" } else { "\n" }))
+}
+
+fn is_excluded_name(name: &str) -> bool {
+    name.starts_with('.') || EXCLUDED_DIRS.contains(&name)
+}
+
+// Recursively collect project files, skipping hidden entries, excluded
+// directories and unreadable subfolders. Symlinked directories aren't followed.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let read_dir = fs::read_dir(dir).map_err(|e| e.to_string())?;
+
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_excluded_name(&name) {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+        if is_dir {
+            let _ = collect_files(&entry_path, files);
+        } else if entry_path.is_file() {
+            files.push(entry_path);
+        }
+    }
+
+    Ok(())
+}
+
+fn is_binary_file(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut buf = vec![0u8; BINARY_SNIFF_LEN];
+    match fs::File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(n) => buf[..n].contains(&0),
+        Err(_) => true,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TodoItem {
+    pub path: String,
+    pub line: usize,
+    pub marker: String,
+    pub text: String,
+}
+
+fn todo_markers(app: &tauri::AppHandle) -> Vec<String> {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(TODO_MARKERS_KEY))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok())
+        .filter(|markers| !markers.is_empty())
+        .unwrap_or_else(|| DEFAULT_TODO_MARKERS.iter().map(|m| m.to_string()).collect())
+}
+
+// Find a marker as a whole word, returning it and the text that follows
+fn find_todo_marker<'a>(line: &'a str, markers: &'a [String]) -> Option<(&'a str, &'a str)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    for marker in markers {
+        for (idx, _) in line.match_indices(marker.as_str()) {
+            let end = idx + marker.len();
+            let before_ok = !line[..idx].chars().next_back().is_some_and(is_word);
+            let after_ok = !line[end..].chars().next().is_some_and(is_word);
+
+            if before_ok && after_ok {
+                let text = line[end..].trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+                return Some((marker.as_str(), text.trim_end()));
+            }
+        }
+    }
+
+    None
+}
+
+#[tauri::command]
+async fn find_todos(app: tauri::AppHandle, root: String) -> Result<Vec<TodoItem>, String> {
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let markers = todo_markers(&app);
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    let mut todos = Vec::new();
+
+    for file in files {
+        if is_binary_file(&file) {
+            continue;
+        }
+
+        let Ok(bytes) = fs::read(&file) else {
+            continue;
+        };
+        let contents = String::from_utf8_lossy(&bytes);
+
+        for (idx, line) in contents.lines().enumerate() {
+            if let Some((marker, text)) = find_todo_marker(line, &markers) {
+                todos.push(TodoItem {
+                    path: file.to_string_lossy().to_string(),
+                    line: idx + 1,
+                    marker: marker.to_string(),
+                    text: text.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(todos)
+}
+
+#[tauri::command]
+async fn set_todo_markers(app: tauri::AppHandle, markers: Vec<String>) -> Result<(), String> {
+    let markers: Vec<String> = markers
+        .into_iter()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    if markers.is_empty() {
+        let _ = store.delete(TODO_MARKERS_KEY);
+    } else {
+        store.set(TODO_MARKERS_KEY, serde_json::to_value(&markers).unwrap());
+    }
+    store.save().map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WindowState {
+    pub width: u32,
+    pub height: u32,
+    pub x: i32,
+    pub y: i32,
+    pub maximized: bool,
+}
+
+fn read_window_state(app: &tauri::AppHandle) -> Option<WindowState> {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(WINDOW_STATE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+}
+
+fn write_window_state(app: &tauri::AppHandle, window_state: &WindowState) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(
+        WINDOW_STATE_KEY,
+        serde_json::to_value(window_state).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn save_window_state(
+    app: tauri::AppHandle,
+    width: u32,
+    height: u32,
+    x: i32,
+    y: i32,
+    maximized: bool,
+) -> Result<(), String> {
+    write_window_state(
+        &app,
+        &WindowState {
+            width,
+            height,
+            x,
+            y,
+            maximized,
+        },
+    )
+}
+
+#[tauri::command]
+fn load_window_state(app: tauri::AppHandle) -> Option<WindowState> {
+    read_window_state(&app)
+}
+
+// Keep saved geometry on a connected monitor, recentring on the primary
+// display when it no longer overlaps any (e.g. a disconnected external screen)
+fn fit_to_monitors(window: &tauri::WebviewWindow, saved: WindowState) -> WindowState {
+    let monitors = window.available_monitors().unwrap_or_default();
+    let right = saved.x.saturating_add(saved.width as i32);
+    let bottom = saved.y.saturating_add(saved.height as i32);
+
+    let on_screen = monitors.iter().any(|monitor| {
+        let pos = monitor.position();
+        let size = monitor.size();
+        saved.x < pos.x + size.width as i32
+            && right > pos.x
+            && saved.y < pos.y + size.height as i32
+            && bottom > pos.y
+    });
+
+    if on_screen {
+        return saved;
+    }
+
+    match window.primary_monitor().ok().flatten() {
+        Some(primary) => {
+            let pos = primary.position();
+            let size = primary.size();
+            let width = saved.width.min(size.width);
+            let height = saved.height.min(size.height);
+
+            WindowState {
+                width,
+                height,
+                x: pos.x + ((size.width - width) / 2) as i32,
+                y: pos.y + ((size.height - height) / 2) as i32,
+                maximized: saved.maximized,
+            }
+        }
+        None => saved,
+    }
+}
+
+fn restore_main_window(app: &tauri::AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+
+    if let Some(saved) = read_window_state(app) {
+        let saved = fit_to_monitors(&window, saved);
+        let _ = window.set_size(tauri::PhysicalSize::new(saved.width, saved.height));
+        let _ = window.set_position(tauri::PhysicalPosition::new(saved.x, saved.y));
+        if saved.maximized {
+            let _ = window.maximize();
+        }
+    }
+
+    // The main window starts hidden so it never flashes at the default size
+    let _ = window.show();
+}
+
+fn capture_window_state(window: &tauri::Window) -> Option<WindowState> {
+    let maximized = window.is_maximized().ok()?;
+
+    // Keep the last restored geometry when closing maximized, so
+    // un-maximizing next time returns to a sensible size
+    if maximized {
+        if let Some(mut previous) = read_window_state(window.app_handle()) {
+            previous.maximized = true;
+            return Some(previous);
+        }
+    }
+
+    let size = window.inner_size().ok()?;
+    let position = window.outer_position().ok()?;
+
+    Some(WindowState {
+        width: size.width,
+        height: size.height,
+        x: position.x,
+        y: position.y,
+        maximized,
+    })
+}
+
+#[tauri::command]
+fn set_window_title(window: tauri::Window, title: String) -> Result<(), String> {
+    window.set_title(&title).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_active_file(window: tauri::Window, state: State<AppState>, path: Option<String>) {
+    let mut buffers = state.buffers.lock().unwrap();
+    buffers
+        .entry(window.label().to_string())
+        .or_default()
+        .active = path;
+}
+
+#[tauri::command]
+fn set_buffer_dirty(window: tauri::Window, state: State<AppState>, path: String, dirty: bool) {
+    let mut buffers = state.buffers.lock().unwrap();
+    let window_buffers = buffers.entry(window.label().to_string()).or_default();
+    if dirty {
+        window_buffers.dirty.insert(path);
+    } else {
+        window_buffers.dirty.remove(&path);
+    }
+}
+
+#[tauri::command]
+fn close_buffer(window: tauri::Window, state: State<AppState>, path: String) {
+    let mut buffers = state.buffers.lock().unwrap();
+    if let Some(window_buffers) = buffers.get_mut(window.label()) {
+        window_buffers.dirty.remove(&path);
+        window_buffers.loaded.remove(&path);
+        if window_buffers.active.as_deref() == Some(path.as_str()) {
+            window_buffers.active = None;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileState {
+    pub path: String,
+    pub dirty: bool,
+    pub exists: bool,
+    // Milliseconds since the Unix epoch
+    pub modified: Option<u64>,
+    pub changed_on_disk: bool,
+}
+
+#[tauri::command]
+fn get_file_states(window: tauri::Window, state: State<AppState>) -> Vec<FileState> {
+    let buffers = state.buffers.lock().unwrap();
+    let Some(window_buffers) = buffers.get(window.label()) else {
+        return Vec::new();
+    };
+
+    let paths: std::collections::BTreeSet<&String> = window_buffers
+        .loaded
+        .keys()
+        .chain(window_buffers.dirty.iter())
+        .collect();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let on_disk = disk_modified(Path::new(path));
+            let loaded = window_buffers.loaded.get(path).copied().flatten();
+
+            FileState {
+                path: path.clone(),
+                dirty: window_buffers.dirty.contains(path),
+                exists: Path::new(path).exists(),
+                modified: on_disk
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64),
+                changed_on_disk: window_buffers.loaded.contains_key(path) && on_disk != loaded,
+            }
+        })
+        .collect()
+}
+
+// Returns false when another window already holds the lock, in which case
+// the file should be opened read-only
+#[tauri::command]
+fn acquire_file_lock(
+    window: tauri::Window,
+    state: State<AppState>,
+    path: String,
+) -> Result<bool, String> {
+    let mut locks = state.file_locks.lock().unwrap();
+    match locks.get(&path) {
+        Some(owner) if owner != window.label() => Ok(false),
+        _ => {
+            locks.insert(path, window.label().to_string());
+            Ok(true)
+        }
+    }
+}
+
+#[tauri::command]
+fn release_file_lock(window: tauri::Window, state: State<AppState>, path: String) {
+    let mut locks = state.file_locks.lock().unwrap();
+    if locks
+        .get(&path)
+        .is_some_and(|owner| owner == window.label())
+    {
+        locks.remove(&path);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpenFileRef {
+    pub window: String,
+    pub path: String,
+    pub active: bool,
+    pub dirty: bool,
+}
+
+// Every buffer open in any window, so the frontend can focus an existing
+// window instead of opening the same file twice
+#[tauri::command]
+fn list_all_open_files(state: State<AppState>) -> Vec<OpenFileRef> {
+    let buffers = state.buffers.lock().unwrap();
+
+    let mut files: Vec<OpenFileRef> = buffers
+        .iter()
+        .flat_map(|(label, window_buffers)| {
+            window_buffers.loaded.keys().map(move |path| OpenFileRef {
+                window: label.clone(),
+                path: path.clone(),
+                active: window_buffers.active.as_ref() == Some(path),
+                dirty: window_buffers.dirty.contains(path),
+            })
+        })
+        .collect();
+
+    files.sort_by(|a, b| a.window.cmp(&b.window).then_with(|| a.path.cmp(&b.path)));
+    files
+}
+
+fn compose_window_title(project: &ProjectState, buffers: &WindowBuffers) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if let Some(active) = &buffers.active {
+        let file_name = Path::new(active)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| active.clone());
+
+        if buffers.dirty.contains(active) {
+            parts.push(format!("● {}", file_name));
+        } else {
+            parts.push(file_name);
+        }
+    }
+
+    if let Some(name) = &project.name {
+        parts.push(name.clone());
+    }
+
+    if parts.is_empty() {
+        "Retro IDE".to_string()
+    } else {
+        parts.join(" — ")
+    }
+}
+
+#[tauri::command]
+fn update_title_from_state(
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<String, String> {
+    let project = state.project(window.label());
+    let buffers = state
+        .buffers
+        .lock()
+        .unwrap()
+        .get(window.label())
+        .cloned()
+        .unwrap_or_default();
+
+    let title = compose_window_title(&project, &buffers);
+    window.set_title(&title).map_err(|e| e.to_string())?;
+
+    Ok(title)
+}
+
+fn current_project_root(state: &AppState, window: &str) -> Result<PathBuf, String> {
+    state
+        .project(window)
+        .path
+        .map(PathBuf::from)
+        .ok_or_else(|| "No project is open".to_string())
+}
+
+// Path of `path` relative to the project root, erroring if it lies outside it
+fn relative_to_project(root: &Path, path: &Path) -> Result<PathBuf, String> {
+    if let Ok(relative) = path.strip_prefix(root) {
+        if !relative
+            .components()
+            .any(|c| c == std::path::Component::ParentDir)
+        {
+            return Ok(relative.to_path_buf());
+        }
+    }
+
+    // Fall back to resolved paths so symlinks and `..` segments still match
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    let path = path.canonicalize().map_err(|e| e.to_string())?;
+    path.strip_prefix(&root)
+        .map(|relative| relative.to_path_buf())
+        .map_err(|_| "Path is outside the project".to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BreadcrumbSegment {
+    pub name: String,
+    pub path: String,
+}
+
+#[tauri::command]
+fn breadcrumb_for(
+    window: tauri::Window,
+    state: State<AppState>,
+    path: String,
+) -> Result<Vec<BreadcrumbSegment>, String> {
+    let root = current_project_root(&state, window.label())?;
+    let relative = relative_to_project(&root, Path::new(&path))?;
+
+    let mut current = root.clone();
+    let mut segments = vec![BreadcrumbSegment {
+        name: state
+            .project(window.label())
+            .name
+            .unwrap_or_else(|| root.to_string_lossy().to_string()),
+        path: root.to_string_lossy().to_string(),
+    }];
+
+    for component in relative.components() {
+        current.push(component);
+        segments.push(BreadcrumbSegment {
+            name: component.as_os_str().to_string_lossy().to_string(),
+            path: current.to_string_lossy().to_string(),
+        });
+    }
+
+    Ok(segments)
+}
+
+fn move_into(window: &tauri::Window, source: &Path, dest_dir: &Path) -> Result<PathBuf, String> {
+    if !source.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    if !dest_dir.is_dir() {
+        return Err("Destination is not a directory".to_string());
+    }
+
+    let file_name = source
+        .file_name()
+        .ok_or_else(|| "Path has no file name".to_string())?;
+    let target = dest_dir.join(file_name);
+
+    if target == source {
+        return Ok(target);
+    }
+
+    if dest_dir.starts_with(source) {
+        return Err("Cannot move a folder into itself".to_string());
+    }
+
+    if target.exists() {
+        return Err("A file or folder with that name already exists".to_string());
+    }
+
+    // Moves onto another volume need the copy fallback
+    rename_with_fallback(window, source, &target)?;
+
+    Ok(target)
+}
+
+#[tauri::command]
+async fn move_path(
+    window: tauri::Window,
+    path: String,
+    dest_dir: String,
+) -> Result<String, String> {
+    let target = move_into(&window, Path::new(&path), Path::new(&dest_dir))?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+fn restrict_to_project(app: &tauri::AppHandle) -> bool {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(RESTRICT_TO_PROJECT_KEY))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+#[tauri::command]
+async fn move_file_dialog(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<Option<String>, String> {
+    let project_root = state.project(window.label()).path;
+
+    let mut dialog = app.dialog().file().set_title("Move To Folder");
+    if let Some(root) = &project_root {
+        dialog = dialog.set_directory(root);
+    }
+
+    let Some(folder) = dialog.blocking_pick_folder() else {
+        return Ok(None);
+    };
+
+    let dest_dir = folder.into_path().map_err(|e| e.to_string())?;
+
+    if restrict_to_project(&app) {
+        let root = project_root.ok_or_else(|| "No project is open".to_string())?;
+        relative_to_project(Path::new(&root), &dest_dir)
+            .map_err(|_| "Destination is outside the project".to_string())?;
+    }
+
+    let target = move_into(&window, Path::new(&path), &dest_dir)?;
+    Ok(Some(target.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+fn common_ancestor(paths: Vec<String>) -> Result<String, String> {
+    // Files contribute their containing folder, so the result is always a directory
+    let dirs: Vec<PathBuf> = paths
+        .iter()
+        .map(|p| {
+            let path = Path::new(p);
+            if path.is_file() {
+                path.parent().unwrap_or(path).to_path_buf()
+            } else {
+                path.to_path_buf()
+            }
+        })
+        .collect();
+
+    let (first, rest) = dirs
+        .split_first()
+        .ok_or_else(|| "No paths given".to_string())?;
+
+    let mut common: Vec<std::path::Component> = first.components().collect();
+
+    for dir in rest {
+        let shared = common
+            .iter()
+            .zip(dir.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        common.truncate(shared);
+    }
+
+    if common.is_empty() {
+        return Err("Paths have no common ancestor".to_string());
+    }
+
+    let ancestor: PathBuf = common.iter().collect();
+    Ok(ancestor.to_string_lossy().to_string())
+}
+
+// Sorted, filtered entries of one directory, as used by project-wide walks
+fn walk_entries(dir: &Path) -> Result<Vec<FileEntry>, String> {
+    let mut entries: Vec<FileEntry> = Vec::new();
+
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_excluded_name(&name) {
+            continue;
+        }
+
+        entries.push(FileEntry {
+            name,
+            path: entry.path().to_string_lossy().to_string(),
+            is_dir: entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
+            is_symlink: entry.file_type().is_ok_and(|t| t.is_symlink()),
+            children: None,
+        });
+    }
+
+    sort_entries(&mut entries);
+
+    Ok(entries)
+}
+
+// Subdirectories are read concurrently; walk_entries has already sorted each
+// level and par_iter_mut keeps that order
+fn read_tree_parallel(dir: &Path, depth: usize, max_depth: Option<usize>) -> Vec<FileEntry> {
+    use rayon::prelude::*;
+
+    let Ok(mut entries) = walk_entries(dir) else {
+        return Vec::new();
+    };
+
+    if max_depth.is_some_and(|max| depth + 1 >= max) {
+        return entries;
+    }
+
+    entries
+        .par_iter_mut()
+        .filter(|entry| entry.is_dir)
+        .for_each(|entry| {
+            entry.children = Some(read_tree_parallel(
+                Path::new(&entry.path),
+                depth + 1,
+                max_depth,
+            ));
+        });
+
+    entries
+}
+
+#[tauri::command]
+async fn read_directory_recursive(
+    state: State<'_, AppState>,
+    path: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<FileEntry>, String> {
+    let _timer = state.time_command("read_directory_recursive");
+    let path = Path::new(&path);
+
+    if !path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    // Fail on an unreadable root rather than returning an empty tree
+    fs::read_dir(path).map_err(|e| e.to_string())?;
+
+    Ok(read_tree_parallel(path, 0, max_depth))
+}
+
+fn write_tree_outline(
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    max_depth: Option<usize>,
+    out: &mut String,
+) {
+    if max_depth.is_some_and(|max| depth >= max) {
+        return;
+    }
+
+    let Ok(entries) = walk_entries(dir) else {
+        return;
+    };
+
+    for (idx, entry) in entries.iter().enumerate() {
+        let last = idx + 1 == entries.len();
+        let branch = if last { "└── " } else { "├── " };
+        let suffix = if entry.is_dir { "/" } else { "" };
+
+        out.push_str(&format!("{}{}{}{}\n", prefix, branch, entry.name, suffix));
+
+        if entry.is_dir {
+            let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            write_tree_outline(
+                Path::new(&entry.path),
+                &child_prefix,
+                depth + 1,
+                max_depth,
+                out,
+            );
+        }
+    }
+}
+
+#[tauri::command]
+async fn export_tree_outline(root: String, max_depth: Option<usize>) -> Result<String, String> {
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let root_name = root_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.clone());
+
+    let mut outline = format!("{}/\n", root_name);
+    write_tree_outline(root_path, "", 0, max_depth, &mut outline);
+
+    Ok(outline)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FileClassification {
+    pub text_count: usize,
+    pub binary_count: usize,
+    pub text_files: Vec<String>,
+    pub binary_files: Vec<String>,
+}
+
+#[tauri::command]
+async fn classify_files(root: String) -> Result<FileClassification, String> {
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    let (binary, text): (Vec<_>, Vec<_>) = files.into_iter().partition(|f| is_binary_file(f));
+
+    let to_strings = |paths: Vec<PathBuf>| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect()
+    };
+
+    Ok(FileClassification {
+        text_count: text.len(),
+        binary_count: binary.len(),
+        text_files: to_strings(text),
+        binary_files: to_strings(binary),
+    })
+}
+
+fn read_text_lossy(path: &Path) -> Option<String> {
+    fs::read(path)
+        .ok()
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Index entry for a file: None for binaries, Some(None) for text files too
+// large to cache, Some(Some(contents)) otherwise
+fn index_entry(path: &Path) -> Option<Option<String>> {
+    if is_binary_file(path) {
+        return None;
+    }
+
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX);
+    if size > MAX_INDEXED_FILE_SIZE {
+        return Some(None);
+    }
+
+    Some(read_text_lossy(path))
+}
+
+impl FileIndex {
+    fn build(root: &Path) -> Result<FileIndex, String> {
+        Self::build_with_progress(root, |_, _| {})
+    }
+
+    // `on_progress` receives (files indexed, total files) as indexing proceeds
+    fn build_with_progress(
+        root: &Path,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<FileIndex, String> {
+        let mut paths = Vec::new();
+        collect_files(root, &mut paths)?;
+
+        let total = paths.len();
+        let files = paths
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, path)| {
+                if (idx + 1) % INDEX_PROGRESS_INTERVAL == 0 || idx + 1 == total {
+                    on_progress(idx + 1, total);
+                }
+                index_entry(&path).map(|contents| (path, contents))
+            })
+            .collect();
+
+        Ok(FileIndex {
+            root: root.to_path_buf(),
+            files,
+            identifiers: None,
+        })
+    }
+
+    fn identifier_counts(&mut self) -> &HashMap<String, usize> {
+        self.identifiers.get_or_insert_with(|| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for contents in self.files.values().flatten() {
+                for word in contents.lines().flat_map(line_identifiers) {
+                    *counts.entry(word.to_string()).or_default() += 1;
+                }
+            }
+            counts
+        })
+    }
+
+    fn stats(&self, started: std::time::Instant) -> IndexStats {
+        let cached: Vec<&String> = self.files.values().flatten().collect();
+        IndexStats {
+            file_count: self.files.len(),
+            cached_file_count: cached.len(),
+            cached_bytes: cached.iter().map(|c| c.len()).sum(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+
+    // Re-read a changed path, dropping it (and anything beneath it) if it's gone
+    fn refresh_path(&mut self, path: &Path) {
+        if !path.starts_with(&self.root) {
+            return;
+        }
+
+        self.files.retain(|indexed, _| !indexed.starts_with(path));
+        self.identifiers = None;
+
+        if is_within_excluded(&self.root, path) {
+            return;
+        }
+
+        let mut paths = Vec::new();
+        if path.is_file() {
+            paths.push(path.to_path_buf());
+        } else if path.is_dir() {
+            let _ = collect_files(path, &mut paths);
+        }
+
+        for path in paths {
+            if let Some(contents) = index_entry(&path) {
+                self.files.insert(path, contents);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexStats {
+    pub file_count: usize,
+    pub cached_file_count: usize,
+    pub cached_bytes: usize,
+    pub duration_ms: u64,
+}
+
+#[tauri::command]
+async fn build_file_index(state: State<'_, AppState>, root: String) -> Result<IndexStats, String> {
+    let _timer = state.time_command("build_file_index");
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let started = std::time::Instant::now();
+    let index = FileIndex::build(root_path)?;
+    let stats = index.stats(started);
+
+    state
+        .file_indexes
+        .lock()
+        .unwrap()
+        .insert(root_path.to_path_buf(), index);
+
+    Ok(stats)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexProgress {
+    pub root: String,
+    pub indexed: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IndexFailed {
+    pub root: String,
+    pub message: String,
+}
+
+// Build the current project's search index on a background thread. Until
+// index-ready fires, searches fall back to walking the disk.
+#[tauri::command]
+fn warm_index(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let root = current_project_root(&state, window.label())?;
+    let label = window.label().to_string();
+
+    std::thread::spawn(move || {
+        let started = std::time::Instant::now();
+        let root_str = root.to_string_lossy().to_string();
+
+        let index = FileIndex::build_with_progress(&root, |indexed, total| {
+            let _ = app.emit_to(
+                label.as_str(),
+                "index-progress",
+                IndexProgress {
+                    root: root_str.clone(),
+                    indexed,
+                    total,
+                },
+            );
+        });
+
+        match index {
+            Ok(index) => {
+                let stats = index.stats(started);
+                app.state::<AppState>()
+                    .file_indexes
+                    .lock()
+                    .unwrap()
+                    .insert(root, index);
+                let _ = app.emit_to(label.as_str(), "index-ready", stats);
+            }
+            // Lets the frontend stop waiting for index-ready
+            Err(message) => {
+                let _ = app.emit_to(
+                    label.as_str(),
+                    "index-failed",
+                    IndexFailed {
+                        root: root_str,
+                        message,
+                    },
+                );
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn invalidate_index(state: State<AppState>) {
+    state.file_indexes.lock().unwrap().clear();
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SearchMatch {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+// `pattern` matches the literal query, case-insensitively when asked. Matching
+// the original line keeps columns right where lowercasing changes byte lengths.
+fn search_text(
+    path: &Path,
+    contents: &str,
+    pattern: &regex::Regex,
+    results: &mut Vec<SearchMatch>,
+) {
+    for (idx, line) in contents.lines().enumerate() {
+        for found in pattern.find_iter(line) {
+            if results.len() >= MAX_SEARCH_RESULTS {
+                return;
+            }
+
+            results.push(SearchMatch {
+                path: path.to_string_lossy().to_string(),
+                line: idx + 1,
+                column: line[..found.start()].chars().count() + 1,
+                text: line.to_string(),
+            });
+        }
+    }
+}
+
+#[tauri::command]
+async fn search_in_files(
+    state: State<'_, AppState>,
+    root: String,
+    query: String,
+    case_sensitive: bool,
+) -> Result<Vec<SearchMatch>, String> {
+    let _timer = state.time_command("search_in_files");
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut results = Vec::new();
+    if query.is_empty() {
+        return Ok(results);
+    }
+
+    let pattern = regex::RegexBuilder::new(&regex::escape(&query))
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Use the cached index when one exists for this root
+    let indexes = state.file_indexes.lock().unwrap();
+    if let Some(index) = indexes.get(root_path) {
+        for (path, contents) in &index.files {
+            match contents {
+                Some(contents) => search_text(path, contents, &pattern, &mut results),
+                None => {
+                    if let Some(contents) = read_text_lossy(path) {
+                        search_text(path, &contents, &pattern, &mut results);
+                    }
+                }
+            }
+        }
+        return Ok(results);
+    }
+    drop(indexes);
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    for file in files {
+        if results.len() >= MAX_SEARCH_RESULTS {
+            break;
+        }
+        if is_binary_file(&file) {
+            continue;
+        }
+        if let Some(contents) = read_text_lossy(&file) {
+            search_text(&file, &contents, &pattern, &mut results);
+        }
+    }
+
+    Ok(results)
+}
+
+fn read_settings_snapshot(path: &Path) -> serde_json::Map<String, serde_json::Value> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// Watch the settings store file and emit `settings-changed` with the keys
+// that differ, whether changed by another window or edited externally
+fn watch_settings(app: &tauri::AppHandle) -> Result<(), String> {
+    use notify::Watcher;
+
+    let store_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(STORE_FILE);
+    let store_dir = store_path
+        .parent()
+        .ok_or_else(|| "Settings path has no parent".to_string())?
+        .to_path_buf();
+    fs::create_dir_all(&store_dir).map_err(|e| e.to_string())?;
+
+    let handle = app.clone();
+    let watched_path = store_path.clone();
+    let mut snapshot = read_settings_snapshot(&store_path);
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(event.kind, notify::EventKind::Access(_))
+            || !event.paths.iter().any(|p| p == &watched_path)
+        {
+            return;
+        }
+
+        let current = read_settings_snapshot(&watched_path);
+        let mut changed: Vec<String> = current
+            .iter()
+            .filter(|(key, value)| snapshot.get(*key) != Some(*value))
+            .map(|(key, _)| key.clone())
+            .chain(
+                snapshot
+                    .keys()
+                    .filter(|key| !current.contains_key(*key))
+                    .cloned(),
+            )
+            .collect();
+
+        if changed.is_empty() {
+            return;
+        }
+        changed.sort();
+        snapshot = current;
+
+        // Pick up external edits in the in-memory store as well
+        if let Ok(store) = handle.store(STORE_FILE) {
+            let _ = store.reload();
+        }
+
+        let _ = handle.emit("settings-changed", changed);
+    })
+    .map_err(|e| e.to_string())?;
+
+    watcher
+        .watch(&store_dir, notify::RecursiveMode::NonRecursive)
+        .map_err(|e| e.to_string())?;
+
+    *app.state::<AppState>().settings_watcher.lock().unwrap() = Some(watcher);
+
+    Ok(())
+}
+
+#[tauri::command]
+fn get_theme(app: tauri::AppHandle) -> String {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(THEME_KEY))
+        .and_then(|value| value.as_str().map(|theme| theme.to_string()))
+        .unwrap_or_else(|| DEFAULT_THEME.to_string())
+}
+
+#[tauri::command]
+async fn set_theme(app: tauri::AppHandle, theme: String) -> Result<(), String> {
+    let theme = theme.trim().to_string();
+    if theme.is_empty() {
+        return Err("Theme cannot be empty".to_string());
+    }
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(THEME_KEY, serde_json::to_value(&theme).unwrap());
+    store.save().map_err(|e| e.to_string())?;
+
+    // Every window follows the theme, not just the one that changed it
+    app.emit("theme-changed", theme).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EditorFont {
+    pub family: String,
+    pub size: u32,
+}
+
+impl Default for EditorFont {
+    fn default() -> Self {
+        EditorFont {
+            family: DEFAULT_FONT_FAMILY.to_string(),
+            size: DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+#[tauri::command]
+fn get_editor_font(app: tauri::AppHandle) -> EditorFont {
+    let stored: Option<EditorFont> = app
+        .store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(EDITOR_FONT_KEY))
+        .and_then(|value| serde_json::from_value(value).ok());
+
+    match stored {
+        Some(font) => EditorFont {
+            family: if font.family.trim().is_empty() {
+                DEFAULT_FONT_FAMILY.to_string()
+            } else {
+                font.family
+            },
+            size: font.size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE),
+        },
+        None => EditorFont::default(),
+    }
+}
+
+#[tauri::command]
+async fn set_editor_font(app: tauri::AppHandle, family: String, size: u32) -> Result<(), String> {
+    if !(MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&size) {
+        return Err(format!(
+            "Font size must be between {} and {}",
+            MIN_FONT_SIZE, MAX_FONT_SIZE
+        ));
+    }
+
+    let family = family.trim();
+    let font = EditorFont {
+        family: if family.is_empty() {
+            DEFAULT_FONT_FAMILY.to_string()
+        } else {
+            family.to_string()
+        },
+        size,
+    };
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(
+        EDITOR_FONT_KEY,
+        serde_json::to_value(&font).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())?;
+
+    app.emit("font-changed", font).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_monospace_fonts() -> Result<Vec<String>, String> {
+    use font_kit::source::SystemSource;
+
+    let source = SystemSource::new();
+    let families = source.all_families().map_err(|e| e.to_string())?;
+
+    let mut monospace: Vec<String> = families
+        .into_iter()
+        .filter(|family| {
+            // A family counts as monospace if its first face loads and says so
+            source
+                .select_family_by_name(family)
+                .ok()
+                .and_then(|handle| handle.fonts().first().and_then(|f| f.load().ok()))
+                .is_some_and(|font| font.is_monospace())
+        })
+        .collect();
+
+    monospace.sort_by_key(|family| family.to_lowercase());
+    monospace.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+    Ok(monospace)
+}
+
+// Build the native app menu; also used to rebuild it after settings change
+fn build_app_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    // App menu items (macOS "Retro IDE" menu)
+    let about = MenuItemBuilder::with_id("about", "About Retro IDE").build(app)?;
+
+    // File menu items
+    let open_project = MenuItemBuilder::with_id("open_project", "Open Project...")
+        .accelerator("CmdOrCtrl+O")
+        .build(app)?;
+    let close_project = MenuItemBuilder::with_id("close_project", "Close Project").build(app)?;
+    let save_file = MenuItemBuilder::with_id("save_file", "Save")
+        .accelerator("CmdOrCtrl+S")
+        .build(app)?;
+
+    // Edit menu items
+    let undo = MenuItemBuilder::with_id("undo", "Undo")
+        .accelerator("CmdOrCtrl+Z")
+        .build(app)?;
+    let redo = MenuItemBuilder::with_id("redo", "Redo")
+        .accelerator("CmdOrCtrl+Shift+Z")
+        .build(app)?;
+    let cut = MenuItemBuilder::with_id("cut", "Cut")
+        .accelerator("CmdOrCtrl+X")
+        .build(app)?;
+    let copy = MenuItemBuilder::with_id("copy", "Copy")
+        .accelerator("CmdOrCtrl+C")
+        .build(app)?;
+    let paste = MenuItemBuilder::with_id("paste", "Paste")
+        .accelerator("CmdOrCtrl+V")
+        .build(app)?;
+    let select_all = MenuItemBuilder::with_id("select_all", "Select All")
+        .accelerator("CmdOrCtrl+A")
+        .build(app)?;
+
+    // Build App submenu (macOS application menu)
+    let app_menu = SubmenuBuilder::new(app, "Retro IDE")
+        .item(&about)
+        .separator()
+        .quit()
+        .build()?;
+
+    // Build File submenu
+    let file_menu = SubmenuBuilder::new(app, "File")
+        .item(&open_project)
+        .item(&close_project)
+        .separator()
+        .item(&save_file)
+        .build()?;
+
+    // Build Edit submenu
+    let edit_menu = SubmenuBuilder::new(app, "Edit")
+        .item(&undo)
+        .item(&redo)
+        .separator()
+        .item(&cut)
+        .item(&copy)
+        .item(&paste)
+        .separator()
+        .item(&select_all)
+        .build()?;
+
+    // Build the full menu
+    MenuBuilder::new(app)
+        .item(&app_menu)
+        .item(&file_menu)
+        .item(&edit_menu)
+        .build()
+}
+
+#[tauri::command]
+fn rebuild_menu(app: tauri::AppHandle) -> Result<(), String> {
+    let menu = build_app_menu(&app).map_err(|e| e.to_string())?;
+    app.set_menu(menu).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn confirm_dialog(
+    app: tauri::AppHandle,
+    title: String,
+    message: String,
+) -> Result<bool, String> {
+    use tauri_plugin_dialog::{MessageDialogButtons, MessageDialogKind};
+
+    let confirmed = app
+        .dialog()
+        .message(message)
+        .title(title)
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancel)
+        .blocking_show();
+
+    Ok(confirmed)
+}
+
+fn message_kind(kind: &str) -> Result<tauri_plugin_dialog::MessageDialogKind, String> {
+    use tauri_plugin_dialog::MessageDialogKind;
+
+    match kind {
+        "info" => Ok(MessageDialogKind::Info),
+        "warning" => Ok(MessageDialogKind::Warning),
+        "error" => Ok(MessageDialogKind::Error),
+        _ => Err(format!("Unknown message kind: {}", kind)),
+    }
+}
+
+// Show a native message box without blocking; backend tasks that fail in the
+// background report through this rather than inventing their own UI
+fn show_user_message(
+    app: &tauri::AppHandle,
+    kind: tauri_plugin_dialog::MessageDialogKind,
+    title: &str,
+    message: &str,
+) {
+    app.dialog()
+        .message(message)
+        .title(title)
+        .kind(kind)
+        .show(|_| {});
+}
+
+#[tauri::command]
+fn show_message(
+    app: tauri::AppHandle,
+    kind: String,
+    title: String,
+    message: String,
+) -> Result<(), String> {
+    show_user_message(&app, message_kind(&kind)?, &title, &message);
+    Ok(())
+}
+
+fn copy_entry(source: &Path, dest: &Path) -> Result<(), String> {
+    if !source.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    if dest.exists() {
+        return Err("A file or folder with that name already exists".to_string());
+    }
+
+    if source.is_dir() {
+        if dest.starts_with(source) {
+            return Err("Cannot copy a folder into itself".to_string());
+        }
+        copy_dir_recursive(source, dest)
+    } else {
+        fs::copy(source, dest)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[tauri::command]
+async fn copy_path(source: String, dest: String) -> Result<(), String> {
+    copy_entry(Path::new(&source), Path::new(&dest))
+}
+
+// First free sibling name: "name copy.ext", then "name copy 2.ext", ...
+fn duplicate_name(path: &Path) -> Result<PathBuf, String> {
+    let (stem, ext) = if path.is_dir() {
+        (path.file_name(), None)
+    } else {
+        (path.file_stem(), path.extension())
+    };
+    let stem = stem
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| "Path has no file name".to_string())?;
+    let ext = ext
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|n| {
+            let suffix = if n == 1 {
+                " copy".to_string()
+            } else {
+                format!(" copy {}", n)
+            };
+            path.with_file_name(format!("{}{}{}", stem, suffix, ext))
+        })
+        .find(|candidate| !candidate.exists())
+        .ok_or_else(|| "No free name for duplicate".to_string())
+}
+
+#[tauri::command]
+async fn duplicate_file(path: String) -> Result<String, String> {
+    let source = Path::new(&path);
+
+    if !source.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    let dest = duplicate_name(source)?;
+    copy_entry(source, &dest)?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+// Language id for a path, mirroring the frontend's detectLanguage table
+fn language_for_path(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "asm" | "s" | "inc" => "asm",
+        "bas" => "basic",
+        "c" | "h" => "c",
+        "cpp" | "cxx" | "cc" | "hpp" | "hxx" => "cpp",
+        "py" | "pyw" | "pyi" => "python",
+        "sh" | "bash" | "zsh" | "fish" | "ksh" => "shell",
+        "md" | "markdown" => "markdown",
+        _ => "text",
+    }
+}
+
+#[tauri::command]
+fn detect_language(path: String) -> String {
+    language_for_path(Path::new(&path)).to_string()
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub scope: String,
+}
+
+fn build_syntax_set() -> Result<syntect::parsing::SyntaxSet, String> {
+    use syntect::parsing::{SyntaxDefinition, SyntaxSet};
+
+    let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+    for source in BUNDLED_SYNTAXES {
+        let definition = SyntaxDefinition::load_from_str(source, true, None)
+            .map_err(|e| format!("Failed to load bundled syntax: {}", e))?;
+        builder.add(definition);
+    }
+    Ok(builder.build())
+}
+
+fn syntax_for<'a>(
+    set: &'a syntect::parsing::SyntaxSet,
+    language: &str,
+    path: &Path,
+) -> &'a syntect::parsing::SyntaxReference {
+    // Map the editor's language ids onto syntect syntax names
+    let name = match language {
+        "asm" | "asm6502" => Some("6502 Assembly"),
+        "asm6809" => Some("6809 Assembly"),
+        "basic" | "basic-ecb" | "basic-cbm" => Some("BASIC"),
+        "c" => Some("C"),
+        "cpp" => Some("C++"),
+        "python" => Some("Python"),
+        "shell" => Some("Bourne Again Shell (bash)"),
+        "markdown" => Some("Markdown"),
+        _ => None,
+    };
+
+    name.and_then(|name| set.find_syntax_by_name(name))
+        .or_else(|| set.find_syntax_by_name(language))
+        .or_else(|| set.find_syntax_by_token(language))
+        .or_else(|| {
+            path.extension()
+                .and_then(|ext| set.find_syntax_by_extension(&ext.to_string_lossy()))
+        })
+        .unwrap_or_else(|| set.find_syntax_plain_text())
+}
+
+fn tokenize(
+    set: &syntect::parsing::SyntaxSet,
+    syntax: &syntect::parsing::SyntaxReference,
+    text: &str,
+) -> Result<Vec<Token>, String> {
+    use syntect::parsing::{ParseState, ScopeStack};
+    use syntect::util::LinesWithEndings;
+
+    let mut parse_state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut line_start = 0;
+
+    // Text covered only by the syntax's root scope isn't worth a token
+    let mut push_token = |stack: &ScopeStack, start: usize, end: usize| {
+        if start >= end || stack.as_slice().len() <= 1 {
+            return;
+        }
+        let scope = stack
+            .as_slice()
+            .last()
+            .map(|s| s.build_string())
+            .unwrap_or_default();
+
+        match tokens.last_mut() {
+            Some(last) if last.end == start && last.scope == scope => last.end = end,
+            _ => tokens.push(Token { start, end, scope }),
+        }
+    };
+
+    for line in LinesWithEndings::from(text) {
+        let ops = parse_state
+            .parse_line(line, set)
+            .map_err(|e| e.to_string())?;
+
+        let mut pos = 0;
+        for (idx, op) in ops {
+            push_token(&stack, line_start + pos, line_start + idx);
+            stack.apply(&op).map_err(|e| format!("{:?}", e))?;
+            pos = idx;
+        }
+        push_token(&stack, line_start + pos, line_start + line.len());
+
+        line_start += line.len();
+    }
+
+    Ok(tokens)
+}
+
+#[tauri::command]
+async fn highlight_file(
+    state: State<'_, AppState>,
+    path: String,
+    language: Option<String>,
+) -> Result<Vec<Token>, String> {
+    let _timer = state.time_command("highlight_file");
+    let file_path = Path::new(&path);
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let metadata = fs::metadata(file_path).map_err(|e| e.to_string())?;
+    if metadata.len() > MAX_HIGHLIGHT_SIZE {
+        return Err("File is too large to highlight".to_string());
+    }
+
+    let language = language.unwrap_or_else(|| language_for_path(file_path).to_string());
+    let modified = metadata.modified().map_err(|e| e.to_string())?;
+
+    if let Some(cached) = state.highlight_cache.lock().unwrap().get(file_path) {
+        if cached.modified == modified && cached.language == language {
+            return Ok(cached.tokens.clone());
+        }
+    }
+
+    let text = read_text_lossy(file_path).ok_or_else(|| "Failed to read file".to_string())?;
+
+    let tokens = {
+        let mut syntax_set = state.syntax_set.lock().unwrap();
+        if syntax_set.is_none() {
+            *syntax_set = Some(build_syntax_set()?);
+        }
+        let set = syntax_set.as_ref().unwrap();
+        tokenize(set, syntax_for(set, &language, file_path), &text)?
+    };
+
+    state.highlight_cache.lock().unwrap().insert(
+        file_path.to_path_buf(),
+        CachedHighlight {
+            modified,
+            language,
+            tokens: tokens.clone(),
+        },
+    );
+
+    Ok(tokens)
+}
+
+#[tauri::command]
+async fn load_syntax_definition(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<String, String> {
+    use syntect::parsing::SyntaxDefinition;
+
+    let file_path = Path::new(&path);
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let ext = file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if ext == "tmlanguage" || ext == "plist" {
+        return Err(
+            "TextMate grammars must be converted to .sublime-syntax before loading".to_string(),
+        );
+    }
+
+    let source = fs::read_to_string(file_path).map_err(|e| e.to_string())?;
+    let fallback_name = file_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string());
+    let definition = SyntaxDefinition::load_from_str(&source, true, fallback_name.as_deref())
+        .map_err(|e| format!("Invalid syntax definition: {}", e))?;
+    let name = definition.name.clone();
+
+    let mut syntax_set = state.syntax_set.lock().unwrap();
+    let mut builder = match syntax_set.take() {
+        Some(set) => set,
+        None => build_syntax_set()?,
+    }
+    .into_builder();
+    builder.add(definition);
+    *syntax_set = Some(builder.build());
+
+    // Cached tokens may have come from a syntax this one now overrides
+    state.highlight_cache.lock().unwrap().clear();
+
+    Ok(name)
+}
+
+// Per-project settings live in the store under PROJECT_SETTINGS_KEY, keyed by project path
+fn read_project_setting(
+    app: &tauri::AppHandle,
+    project: &str,
+    key: &str,
+) -> Option<serde_json::Value> {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(PROJECT_SETTINGS_KEY))
+        .and_then(|all| {
+            all.get(project)
+                .and_then(|settings| settings.get(key))
+                .cloned()
+        })
+}
+
+fn write_project_setting(
+    app: &tauri::AppHandle,
+    project: &str,
+    key: &str,
+    value: Option<serde_json::Value>,
+) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+
+    let mut all = store
+        .get(PROJECT_SETTINGS_KEY)
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+    let mut settings = all
+        .get(project)
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+
+    match value {
+        Some(value) => {
+            settings.insert(key.to_string(), value);
+        }
+        None => {
+            settings.remove(key);
+        }
+    }
+
+    if settings.is_empty() {
+        all.remove(project);
+    } else {
+        all.insert(project.to_string(), serde_json::Value::Object(settings));
+    }
+
+    store.set(PROJECT_SETTINGS_KEY, serde_json::Value::Object(all));
+    store.save().map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FormatterConfig {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+fn formatter_configs(app: &tauri::AppHandle, project: &str) -> HashMap<String, FormatterConfig> {
+    read_project_setting(app, project, FORMATTERS_SETTING)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_formatter_config(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    language: String,
+) -> Result<Option<FormatterConfig>, String> {
+    let root = current_project_root(&state, window.label())?;
+    Ok(formatter_configs(&app, &root.to_string_lossy()).remove(&language))
+}
+
+#[tauri::command]
+async fn set_formatter_config(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    language: String,
+    config: Option<FormatterConfig>,
+) -> Result<(), String> {
+    let root = current_project_root(&state, window.label())?;
+    let project = root.to_string_lossy();
+
+    let mut configs = formatter_configs(&app, &project);
+    match config {
+        Some(config) => {
+            configs.insert(language, config);
+        }
+        None => {
+            configs.remove(&language);
+        }
+    }
+
+    let value = serde_json::to_value(&configs).map_err(|e| e.to_string())?;
+    write_project_setting(&app, &project, FORMATTERS_SETTING, Some(value))
+}
+
+// Run a tool with `input` on stdin, returning its stdout. The command is
+// built through the shell plugin and run as a std process for stdin control.
+fn run_with_stdin(
+    app: &tauri::AppHandle,
+    program: &str,
+    args: &[String],
+    cwd: Option<&Path>,
+    input: &[u8],
+) -> Result<std::process::Output, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut command = app.shell().command(program).args(args);
+    if let Some(cwd) = cwd {
+        command = command.current_dir(cwd);
+    }
+
+    let mut command: std::process::Command = command.into();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    // Write from a separate thread so a tool that streams output can't deadlock us
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| "Failed to open stdin".to_string())?;
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let _ = writer.join();
+
+    Ok(output)
+}
+
+#[tauri::command]
+async fn format_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+    formatter: String,
+    args: Vec<String>,
+) -> Result<String, String> {
+    let file_path = PathBuf::from(&path);
+    let contents = read_text_file(&file_path)?.contents;
+
+    // Without an explicit formatter, fall back to the project's config for the language
+    let (program, args) = if formatter.trim().is_empty() {
+        let root = current_project_root(&state, window.label())?;
+        let language = language_for_path(&file_path);
+        let config = formatter_configs(&app, &root.to_string_lossy())
+            .remove(language)
+            .ok_or_else(|| format!("No formatter configured for {}", language))?;
+        (config.command, config.args)
+    } else {
+        (formatter, args)
+    };
+
+    let cwd = file_path.parent().map(|p| p.to_path_buf());
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        run_with_stdin(&app, &program, &args, cwd.as_deref(), contents.as_bytes())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("Formatter exited with {}", output.status)
+        } else {
+            stderr
+        });
+    }
+
+    String::from_utf8(output.stdout).map_err(|_| "Formatter output is not valid UTF-8".to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: String,
+    pub message: String,
+}
+
+// Parse one line of tool output using named groups `line`, `column`,
+// `severity` and `message`; only `line` is required
+fn parse_diagnostic(pattern: &regex::Regex, output_line: &str) -> Option<Diagnostic> {
+    let captures = pattern.captures(output_line.trim_end())?;
+    let line = captures.name("line")?.as_str().parse().ok()?;
+
+    Some(Diagnostic {
+        line,
+        column: captures
+            .name("column")
+            .and_then(|c| c.as_str().parse().ok())
+            .unwrap_or(1),
+        severity: captures
+            .name("severity")
+            .map(|s| s.as_str().to_lowercase())
+            .unwrap_or_else(|| "warning".to_string()),
+        message: captures
+            .name("message")
+            .map(|m| m.as_str().trim().to_string())
+            .unwrap_or_else(|| output_line.trim().to_string()),
+    })
+}
+
+fn linter_pattern(app: &tauri::AppHandle, linter: &str) -> Result<regex::Regex, String> {
+    let configured = app
+        .store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(LINTER_PATTERNS_KEY))
+        .and_then(|patterns| {
+            patterns
+                .get(linter)
+                .and_then(|p| p.as_str().map(String::from))
+        });
+
+    regex::Regex::new(configured.as_deref().unwrap_or(DEFAULT_DIAGNOSTIC_PATTERN))
+        .map_err(|e| format!("Invalid diagnostic pattern for {}: {}", linter, e))
+}
+
+#[tauri::command]
+async fn set_linter_pattern(
+    app: tauri::AppHandle,
+    linter: String,
+    pattern: Option<String>,
+) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let mut patterns = store
+        .get(LINTER_PATTERNS_KEY)
+        .and_then(|v| v.as_object().cloned())
+        .unwrap_or_default();
+
+    match pattern {
+        Some(pattern) => {
+            let compiled = regex::Regex::new(&pattern).map_err(|e| e.to_string())?;
+            if !compiled.capture_names().any(|name| name == Some("line")) {
+                return Err("Pattern must have a named `line` group".to_string());
+            }
+            patterns.insert(linter, serde_json::Value::String(pattern));
+        }
+        None => {
+            patterns.remove(&linter);
+        }
+    }
+
+    store.set(LINTER_PATTERNS_KEY, serde_json::Value::Object(patterns));
+    store.save().map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Clone)]
+struct LintProgress {
+    path: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[tauri::command]
+async fn lint_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    path: String,
+    linter: String,
+    args: Vec<String>,
+) -> Result<Vec<Diagnostic>, String> {
+    use tauri_plugin_shell::process::CommandEvent;
+
+    let file_path = Path::new(&path);
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let pattern = linter_pattern(&app, &linter)?;
+
+    // The file being linted is passed as the final argument
+    let mut command = app.shell().command(&linter).args(&args).arg(&path);
+    if let Some(parent) = file_path.parent() {
+        command = command.current_dir(parent);
+    }
+
+    let (mut events, _child) = command
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", linter, e))?;
+
+    let mut diagnostics = Vec::new();
+
+    while let Some(event) = events.recv().await {
+        match event {
+            CommandEvent::Stdout(bytes) | CommandEvent::Stderr(bytes) => {
+                let batch: Vec<Diagnostic> = String::from_utf8_lossy(&bytes)
+                    .lines()
+                    .filter_map(|line| parse_diagnostic(&pattern, line))
+                    .collect();
+
+                // Stream partial results so slow linters still show squiggles early
+                if !batch.is_empty() {
+                    let _ = app.emit_to(
+                        window.label(),
+                        "lint-progress",
+                        LintProgress {
+                            path: path.clone(),
+                            diagnostics: batch.clone(),
+                        },
+                    );
+                    diagnostics.extend(batch);
+                }
+            }
+            CommandEvent::Error(e) => return Err(e),
+            CommandEvent::Terminated(_) => break,
+            _ => {}
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProjectManifest {
+    pub assembler: Option<String>,
+    pub assembler_args: Vec<String>,
+    pub emulator: Option<String>,
+    pub emulator_args: Vec<String>,
+    pub entry: Option<String>,
+}
+
+fn read_project_manifest(root: &Path) -> Result<Option<ProjectManifest>, String> {
+    let path = root.join(PROJECT_CONFIG_DIR).join(PROJECT_MANIFEST);
+
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    toml::from_str(&contents)
+        .map(Some)
+        .map_err(|e| format!("Invalid {}: {}", PROJECT_MANIFEST, e))
+}
+
+#[tauri::command]
+fn load_project_manifest(
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<Option<ProjectManifest>, String> {
+    let root = current_project_root(&state, window.label())?;
+    read_project_manifest(&root)
+}
+
+#[tauri::command]
+fn save_project_manifest(
+    window: tauri::Window,
+    state: State<AppState>,
+    manifest: ProjectManifest,
+) -> Result<(), String> {
+    let root = current_project_root(&state, window.label())?;
+    let dir = root.join(PROJECT_CONFIG_DIR);
+
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let contents = toml::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(dir.join(PROJECT_MANIFEST), contents).map_err(|e| e.to_string())
+}
+
+// Error formats of common retro assemblers, keyed by executable name
+fn assembler_pattern(assembler: &str) -> Option<&'static str> {
+    match assembler {
+        // ca65: "main.s(12): Error: Unknown opcode"
+        "ca65" | "cl65" => {
+            Some(r"^.*?\((?P<line>\d+)\):\s*(?P<severity>Error|Warning|Note):\s*(?P<message>.+)$")
+        }
+        // ACME: "Error - File main.asm, line 12 (Zone <untitled>): Unknown mnemonic"
+        "acme" => Some(
+            r"^(?P<severity>Error|Warning|Serious error) - File .*?, line (?P<line>\d+).*?:\s*(?P<message>.+)$",
+        ),
+        // DASM: "main.asm (12): error: Unknown Mnemonic 'lad'."
+        "dasm" => Some(
+            r"^.*?\s\((?P<line>\d+)\):\s*(?P<severity>error|warning|fatal):?\s*(?P<message>.+)$",
+        ),
+        // LWASM: "main.asm(12) : ERROR : Bad opcode"
+        "lwasm" => {
+            Some(r"^.*?\((?P<line>\d+)\)\s*:\s*(?P<severity>ERROR|WARNING)\s*:\s*(?P<message>.+)$")
+        }
+        // 64tass, xa and most others use the gcc-like default
+        _ => None,
+    }
+}
+
+fn assembler_diagnostic_pattern(
+    app: &tauri::AppHandle,
+    assembler: &str,
+) -> Result<regex::Regex, String> {
+    let name = Path::new(assembler)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let configured = app
+        .store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(LINTER_PATTERNS_KEY))
+        .is_some_and(|patterns| patterns.get(&name).is_some());
+
+    match assembler_pattern(&name) {
+        Some(pattern) if !configured => regex::Regex::new(pattern).map_err(|e| e.to_string()),
+        _ => linter_pattern(app, &name),
+    }
+}
+
+// Output file named by the usual -o/--output flags, relative to `cwd`. Only
+// DASM takes the file attached as `-oFILE`; elsewhere flags like `-org` would
+// be misread as an output path.
+fn output_path_from_args(assembler: &str, args: &[String], cwd: &Path) -> Option<PathBuf> {
+    let attached_o = Path::new(assembler)
+        .file_stem()
+        .is_some_and(|stem| stem.eq_ignore_ascii_case("dasm"));
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = match arg.as_str() {
+            "-o" | "--output" | "--outfile" => iter.next().cloned(),
+            _ => ["--output=", "--outfile="]
+                .iter()
+                .find_map(|flag| arg.strip_prefix(flag))
+                .or_else(|| arg.strip_prefix("-o").filter(|_| attached_o))
+                .filter(|v| !v.is_empty())
+                .map(String::from),
+        };
+        if let Some(value) = value {
+            return Some(cwd.join(value));
+        }
+    }
+    None
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssembleResult {
+    pub success: bool,
+    pub output_path: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub log: String,
+}
+
+#[tauri::command]
+async fn assemble(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    source_path: String,
+    assembler: String,
+    args: Vec<String>,
+    cwd: String,
+) -> Result<AssembleResult, String> {
+    // An empty assembler falls back to the project manifest, including its args
+    let (assembler, args) = if assembler.is_empty() {
+        let root = current_project_root(&state, window.label())?;
+        let manifest = read_project_manifest(&root)?.unwrap_or_default();
+        let assembler = manifest
+            .assembler
+            .ok_or("No assembler configured for this project")?;
+        let args = if args.is_empty() {
+            manifest.assembler_args
+        } else {
+            args
+        };
+        (assembler, args)
+    } else {
+        (assembler, args)
+    };
+
+    let source = Path::new(&source_path);
+    let cwd_path = Path::new(&cwd);
+
+    if !source.is_file() {
+        return Err("Source file does not exist".to_string());
+    }
+
+    if !cwd_path.is_dir() {
+        return Err("Working directory does not exist".to_string());
+    }
+
+    let pattern = assembler_diagnostic_pattern(&app, &assembler)?;
+    let started = std::time::SystemTime::now();
+
+    let output = app
+        .shell()
+        .command(&assembler)
+        .args(&args)
+        .current_dir(cwd_path)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run {}: {}", assembler, e))?;
+
+    let log = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let diagnostics: Vec<Diagnostic> = log
+        .lines()
+        .filter_map(|line| parse_diagnostic(&pattern, line))
+        .collect();
+
+    let success = output.status.success()
+        && !diagnostics
+            .iter()
+            .any(|d| d.severity.contains("error") || d.severity == "fatal");
+
+    // Prefer an explicit -o; otherwise look for a freshly written file next to the source
+    let output_path = if success {
+        output_path_from_args(&assembler, &args, cwd_path)
+            .or_else(|| {
+                ["prg", "bin", "rom", "o"]
+                    .iter()
+                    .map(|ext| source.with_extension(ext))
+                    .find(|candidate| {
+                        fs::metadata(candidate)
+                            .and_then(|m| m.modified())
+                            .is_ok_and(|modified| modified >= started)
+                    })
+            })
+            .filter(|path| path.is_file())
+            .map(|path| path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    Ok(AssembleResult {
+        success,
+        output_path,
+        diagnostics,
+        log,
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EmulatorEvent {
+    pub rom_path: String,
+    pub pid: u32,
+    pub code: Option<i32>,
+}
+
+#[tauri::command]
+fn get_emulator_path(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<Option<String>, String> {
+    let root = current_project_root(&state, window.label())?;
+    Ok(
+        read_project_setting(&app, &root.to_string_lossy(), EMULATOR_SETTING)
+            .and_then(|v| v.as_str().map(String::from)),
+    )
+}
+
+// An empty `emulator` falls back to the project manifest, then the one last
+// used for the current project
+#[tauri::command]
+fn run_in_emulator(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    rom_path: String,
+    emulator: String,
+    args: Vec<String>,
+) -> Result<(), String> {
+    use tauri_plugin_shell::process::CommandEvent;
+
+    if !Path::new(&rom_path).is_file() {
+        return Err("ROM file does not exist".to_string());
+    }
+
+    let project = current_project_root(&state, window.label())
+        .ok()
+        .map(|root| root.to_string_lossy().to_string());
+
+    let manifest = current_project_root(&state, window.label())
+        .ok()
+        .and_then(|root| read_project_manifest(&root).ok().flatten())
+        .unwrap_or_default();
+
+    let (emulator, args) = if emulator.is_empty() {
+        let emulator = manifest
+            .emulator
+            .or_else(|| {
+                project
+                    .as_deref()
+                    .and_then(|project| read_project_setting(&app, project, EMULATOR_SETTING))
+                    .and_then(|v| v.as_str().map(String::from))
+            })
+            .ok_or("No emulator configured for this project")?;
+        let args = if args.is_empty() {
+            manifest.emulator_args
+        } else {
+            args
+        };
+        (emulator, args)
+    } else {
+        (emulator, args)
+    };
+
+    let (mut events, child) = app
+        .shell()
+        .command(&emulator)
+        .args(&args)
+        .arg(&rom_path)
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", emulator, e))?;
+
+    // The emulator is already running, so failing to remember it is only
+    // reported rather than turned into a launch error
+    if let Some(project) = &project {
+        if let Err(e) = write_project_setting(
+            &app,
+            project,
+            EMULATOR_SETTING,
+            Some(serde_json::Value::String(emulator.clone())),
+        ) {
+            report_error(
+                &app,
+                window.label(),
+                format!("Failed to remember emulator: {}", e),
+            );
+        }
+    }
+
+    let label = window.label().to_string();
+    let pid = child.pid();
+    let _ = app.emit_to(
+        label.as_str(),
+        "emulator-started",
+        EmulatorEvent {
+            rom_path: rom_path.clone(),
+            pid,
+            code: None,
+        },
+    );
+
+    tauri::async_runtime::spawn(async move {
+        let mut code = None;
+        while let Some(event) = events.recv().await {
+            if let CommandEvent::Terminated(payload) = event {
+                code = payload.code;
+                break;
+            }
+        }
+
+        let _ = app.emit_to(
+            label.as_str(),
+            "emulator-exited",
+            EmulatorEvent {
+                rom_path,
+                pid,
+                code,
+            },
+        );
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn save_tree_expansion(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    expanded_paths: Vec<String>,
+) -> Result<(), String> {
+    let root = current_project_root(&state, window.label())?;
+    let value = if expanded_paths.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(expanded_paths).map_err(|e| e.to_string())?)
+    };
+
+    write_project_setting(&app, &root.to_string_lossy(), TREE_EXPANSION_SETTING, value)
+}
+
+#[tauri::command]
+fn load_tree_expansion(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+) -> Result<Vec<String>, String> {
+    let Ok(root) = current_project_root(&state, window.label()) else {
+        return Ok(Vec::new());
+    };
+
+    let paths: Vec<String> =
+        read_project_setting(&app, &root.to_string_lossy(), TREE_EXPANSION_SETTING)
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default();
+
+    // Folders deleted or moved since the last session are dropped
+    Ok(paths
+        .into_iter()
+        .filter(|path| {
+            let path = Path::new(path);
+            path.starts_with(&root) && path.is_dir()
+        })
+        .collect())
+}
+
+// Only web links are handed to the system browser; file:// and custom schemes
+// could launch arbitrary local programs
+#[tauri::command]
+fn open_external_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
+    let url = url.trim();
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_ascii_lowercase())
+        .ok_or("URL must start with http:// or https://")?;
+
+    if scheme != "http" && scheme != "https" {
+        return Err(format!("Refusing to open {}:// URL", scheme));
+    }
+
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| e.to_string())
+}
+
+// Newline used for files the IDE generates; "lf" unless set otherwise
+#[tauri::command]
+fn get_default_line_ending(app: tauri::AppHandle) -> String {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(DEFAULT_LINE_ENDING_KEY))
+        .and_then(|value| value.as_str().map(|ending| ending.to_string()))
+        .filter(|ending| ending == "lf" || ending == "crlf")
+        .unwrap_or_else(|| "lf".to_string())
+}
+
+#[tauri::command]
+async fn set_default_line_ending(app: tauri::AppHandle, line_ending: String) -> Result<(), String> {
+    let line_ending = line_ending.trim().to_lowercase();
+    if line_ending != "lf" && line_ending != "crlf" {
+        return Err(format!("Unknown line ending: {}", line_ending));
+    }
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.set(
+        DEFAULT_LINE_ENDING_KEY,
+        serde_json::to_value(&line_ending).unwrap(),
+    );
+    store.save().map_err(|e| e.to_string())
+}
+
+fn with_line_ending(text: &str, crlf: bool) -> String {
+    let lf = text.replace("\r\n", "\n");
+    if crlf {
+        lf.replace('\n', "\r\n")
+    } else {
+        lf
+    }
+}
+
+// Rewrites generated or template text with the configured newline
+fn apply_default_line_ending(app: &tauri::AppHandle, text: &str) -> String {
+    with_line_ending(text, get_default_line_ending(app.clone()) == "crlf")
+}
+
+// Normalize the newlines of every text file a new project got from its
+// template. Binaries and files that aren't UTF-8 are left as copied.
+fn apply_default_line_ending_to_tree(app: &tauri::AppHandle, root: &Path) -> Result<(), String> {
+    let crlf = get_default_line_ending(app.clone()) == "crlf";
+    let mut files = Vec::new();
+    collect_export_files(root, &mut files)?;
+
+    for file in files {
+        if is_binary_file(&file) {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let converted = with_line_ending(&contents, crlf);
+        if converted != contents {
+            fs::write(&file, converted).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+// Toolchain-specific ignore rules, appended to a common base
+fn gitignore_template(template: &str) -> Option<&'static str> {
+    match template {
+        "generic" => Some(""),
+        "cc65" => Some("# cc65\n*.lst\n*.map\n*.lbl\n*.dbg\n*.prg\n"),
+        "acme" => Some("# ACME\n*.prg\n*.lbl\n*.sym\n*.rep\n"),
+        "dasm" => Some("# DASM\n*.lst\n*.sym\n"),
+        "lwtools" => Some("# LWTOOLS\n*.lst\n*.map\n*.dsk\n"),
+        _ => None,
+    }
+}
+
+#[tauri::command]
+fn create_gitignore(
+    app: tauri::AppHandle,
+    root: String,
+    template: String,
+    overwrite: Option<bool>,
+) -> Result<(), String> {
+    let root = Path::new(&root);
+    if !root.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+
+    let rules = gitignore_template(&template)
+        .ok_or_else(|| format!("Unknown .gitignore template: {}", template))?;
+
+    let path = root.join(".gitignore");
+    if path.exists() && !overwrite.unwrap_or(false) {
+        return Err(".gitignore already exists".to_string());
+    }
+
+    let contents = format!(
+        "# Build output\n*.o\n*.bin\nbuild/\nout/\n\n# OS\n.DS_Store\nThumbs.db\n\n{}",
+        rules
+    );
+    let contents = apply_default_line_ending(&app, &(contents.trim_end().to_string() + "\n"));
+    fs::write(path, contents).map_err(|e| e.to_string())
+}
+
+fn recovery_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(RECOVERY_DIR))
+        .map_err(|e| e.to_string())
+}
+
+// Snapshots are keyed by a hash of the original path so each file has at most one
+fn recovery_id(path: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    // A stable digest, so snapshots still match after a toolchain upgrade
+    hex_digest(&Sha256::digest(path.as_bytes())[..8])
+}
+
+fn recovery_file_name(path: &str) -> String {
+    format!("{}.json", recovery_id(path))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecoveryEntry {
+    pub id: String,
+    pub path: String,
+    pub contents: String,
+    // Milliseconds since the Unix epoch
+    pub saved_at: u64,
+}
+
+#[tauri::command]
+async fn write_recovery_snapshot(
+    app: tauri::AppHandle,
+    path: String,
+    contents: String,
+) -> Result<(), String> {
+    let dir = recovery_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let entry = RecoveryEntry {
+        id: recovery_id(&path),
+        saved_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        path,
+        contents,
+    };
+    let json = serde_json::to_vec(&entry).map_err(|e| e.to_string())?;
+
+    // Write then rename so a crash mid-write never leaves a truncated snapshot
+    let target = dir.join(format!("{}.json", entry.id));
+    let temp = dir.join(format!("{}.tmp", entry.id));
+    fs::write(&temp, json).map_err(|e| e.to_string())?;
+    fs::rename(&temp, &target).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn list_recovery_snapshots(app: tauri::AppHandle) -> Result<Vec<RecoveryEntry>, String> {
+    let dir = recovery_dir(&app)?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries: Vec<RecoveryEntry> = fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| fs::read(path).ok())
+        .filter_map(|bytes| serde_json::from_slice(&bytes).ok())
+        .collect();
+
+    entries.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+    Ok(entries)
+}
+
+#[tauri::command]
+async fn discard_recovery_snapshot(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    // Ids are hex hashes; anything else could escape the recovery directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Invalid snapshot id".to_string());
+    }
+
+    let path = recovery_dir(&app)?.join(format!("{}.json", id));
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+// Remove snapshots older than the configured age, returning how many were pruned
+fn prune_recovery_snapshots(app: &tauri::AppHandle) -> Result<usize, String> {
+    let dir = recovery_dir(app)?;
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let max_age_days = app
+        .store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(RECOVERY_MAX_AGE_KEY))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_RECOVERY_MAX_AGE_DAYS);
+    let max_age = std::time::Duration::from_secs(max_age_days * 24 * 60 * 60);
+    let now = std::time::SystemTime::now();
+
+    let mut pruned = 0;
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+
+        if expired && fs::remove_file(entry.path()).is_ok() {
+            pruned += 1;
+        }
+    }
+
+    Ok(pruned)
+}
+
+#[tauri::command]
+async fn detect_entry_points(root: String) -> Result<Vec<String>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+
+    let mut entry_points: Vec<PathBuf> = Vec::new();
+
+    // A declared entry always comes first
+    if let Some(entry) = read_project_manifest(root_path)?.and_then(|m| m.entry) {
+        let entry = root_path.join(entry);
+        if entry.is_file() {
+            entry_points.push(entry);
+        }
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    let sources = files
+        .into_iter()
+        .filter(|path| matches!(language_for_path(path), "asm" | "basic"));
+
+    let (named, others): (Vec<PathBuf>, Vec<PathBuf>) = sources.partition(|path| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .is_some_and(|stem| ENTRY_POINT_STEMS.contains(&stem.as_str()))
+    });
+    entry_points.extend(named);
+
+    entry_points.extend(others.into_iter().filter(|path| {
+        fs::metadata(path).is_ok_and(|m| m.len() <= MAX_INDEXED_FILE_SIZE)
+            && fs::read(path).is_ok_and(|bytes| {
+                let text = String::from_utf8_lossy(&bytes).to_lowercase();
+                ENTRY_POINT_MARKERS
+                    .iter()
+                    .any(|marker| text.contains(marker))
+            })
+    }));
+
+    let mut seen = HashSet::new();
+    Ok(entry_points
+        .into_iter()
+        .filter(|path| seen.insert(path.clone()))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+#[tauri::command]
+fn copy_path_to_clipboard(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    path: String,
+    format: String,
+) -> Result<(), String> {
+    let file_path = Path::new(&path);
+
+    let text = match format.as_str() {
+        "absolute" => path.clone(),
+        "relative" => {
+            let root = current_project_root(&state, window.label())?;
+            relative_to_project(&root, file_path)?
+                .to_string_lossy()
+                .to_string()
+        }
+        "filename" => file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or("Path has no file name")?,
+        _ => return Err(format!("Unknown path format: {}", format)),
+    };
+
+    app.clipboard().write_text(text).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExtensionCount {
+    pub extension: String,
+    pub count: usize,
+}
+
+#[tauri::command]
+async fn file_type_histogram(root: String) -> Result<Vec<ExtensionCount>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for file in files {
+        let extension = file
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| "(none)".to_string());
+        *counts.entry(extension).or_default() += 1;
+    }
+
+    let mut histogram: Vec<ExtensionCount> = counts
+        .into_iter()
+        .map(|(extension, count)| ExtensionCount { extension, count })
+        .collect();
+    histogram.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+
+    Ok(histogram)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TaskExited {
+    pub task_id: String,
+    pub code: Option<i32>,
+}
+
+// Run a process in the background, appending its combined stdout/stderr to
+// `output_path` as it arrives. Returns a task id usable with cancel_task.
+#[tauri::command]
+fn run_task_to_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    command: String,
+    args: Vec<String>,
+    cwd: String,
+    output_path: String,
+) -> Result<String, String> {
+    use std::io::Write;
+    use tauri_plugin_shell::process::CommandEvent;
+
+    if !Path::new(&cwd).is_dir() {
+        return Err("Working directory does not exist".to_string());
+    }
+
+    let mut log = fs::File::create(&output_path).map_err(|e| e.to_string())?;
+
+    let (mut events, child) = app
+        .shell()
+        .command(&command)
+        .args(&args)
+        .current_dir(&cwd)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", command, e))?;
+
+    let task_id = format!("task-{}", state.next_task_id.fetch_add(1, Ordering::SeqCst));
+    state.tasks.lock().unwrap().insert(task_id.clone(), child);
+
+    let label = window.label().to_string();
+    let id = task_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut code = None;
+        while let Some(event) = events.recv().await {
+            match event {
+                CommandEvent::Stdout(bytes) | CommandEvent::Stderr(bytes) => {
+                    // Flush per chunk so file watchers see output live
+                    let _ = log.write_all(&bytes).and_then(|_| log.flush());
+                }
+                CommandEvent::Error(e) => {
+                    let _ = writeln!(log, "{}", e);
+                }
+                CommandEvent::Terminated(payload) => {
+                    code = payload.code;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        app.state::<AppState>().tasks.lock().unwrap().remove(&id);
+        let _ = app.emit_to(
+            label.as_str(),
+            "task-exited",
+            TaskExited { task_id: id, code },
+        );
+    });
+
+    Ok(task_id)
+}
+
+#[tauri::command]
+fn cancel_task(state: State<AppState>, task_id: String) -> Result<(), String> {
+    let child = state
+        .tasks
+        .lock()
+        .unwrap()
+        .remove(&task_id)
+        .ok_or("No such running task")?;
+    child.kill().map_err(|e| e.to_string())
+}
+
+// Find the repository containing `path`, or None when it isn't under git
+fn discover_repo(path: &Path) -> Result<Option<git2::Repository>, String> {
+    match git2::Repository::discover(path) {
+        Ok(repo) => Ok(Some(repo)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GitRepoInfo {
+    pub top_level: String,
+    pub is_root: bool,
+    pub is_submodule: bool,
+    pub is_worktree: bool,
+}
+
+#[tauri::command]
+async fn git_repo_info(path: String) -> Result<Option<GitRepoInfo>, String> {
+    let path = Path::new(&path);
+    let Some(repo) = discover_repo(path)? else {
+        return Ok(None);
+    };
+
+    // Bare repositories have no working tree to show in the file tree
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+
+    let top_level = workdir.canonicalize().map_err(|e| e.to_string())?;
+    let is_root = path.canonicalize().is_ok_and(|path| path == top_level);
+
+    // Worktrees and submodules both use a `.git` file pointing elsewhere;
+    // libgit2 can tell worktrees apart directly
+    let is_worktree = repo.is_worktree();
+    let is_submodule = !is_worktree && top_level.join(".git").is_file();
+
+    Ok(Some(GitRepoInfo {
+        top_level: top_level.to_string_lossy().to_string(),
+        is_root,
+        is_submodule,
+        is_worktree,
+    }))
+}
+
+// Ignored directories are reported once and not descended into
+fn collect_ignored(
+    repo: &git2::Repository,
+    workdir: &Path,
+    dir: &Path,
+    ignored: &mut Vec<String>,
+) -> Result<(), String> {
+    let read_dir = fs::read_dir(dir).map_err(|e| e.to_string())?;
+
+    for entry in read_dir.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_excluded_name(&name) {
+            continue;
+        }
+
+        let entry_path = entry.path();
+        let Ok(relative) = entry_path.strip_prefix(workdir) else {
+            continue;
+        };
+
+        if repo.is_path_ignored(relative).map_err(|e| e.to_string())? {
+            ignored.push(entry_path.to_string_lossy().to_string());
+        } else if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            let _ = collect_ignored(repo, workdir, &entry_path, ignored);
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_ignored_paths(root: String) -> Result<Vec<String>, String> {
+    let root = Path::new(&root).canonicalize().map_err(|e| e.to_string())?;
+
+    let Some(repo) = discover_repo(&root)? else {
+        return Ok(Vec::new());
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(Vec::new());
+    };
+    let workdir = workdir.canonicalize().map_err(|e| e.to_string())?;
+
+    let mut ignored = Vec::new();
+    collect_ignored(&repo, &workdir, &root, &mut ignored)?;
+    ignored.sort();
+    Ok(ignored)
+}
+
+// Open the repository containing a file, returning it with the file's path
+// relative to the working tree
+fn repo_for_file(path: &Path) -> Result<(git2::Repository, PathBuf), String> {
+    // Resolve through the parent so files deleted from the working copy still map
+    let parent = path
+        .parent()
+        .ok_or("Invalid path")?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    let path = parent.join(path.file_name().ok_or("Invalid path")?);
+    let repo = discover_repo(&path)?.ok_or("Not a git repository")?;
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working tree")?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+
+    let relative = path
+        .strip_prefix(&workdir)
+        .map(|p| p.to_path_buf())
+        .map_err(|_| "Path is outside the repository".to_string())?;
+    Ok((repo, relative))
+}
+
+fn short_hash(oid: git2::Oid) -> String {
+    oid.to_string().chars().take(7).collect()
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlameLine {
+    pub line: usize,
+    pub hash: String,
+    pub author: String,
+    // Seconds since the Unix epoch; 0 for uncommitted lines
+    pub timestamp: i64,
+}
+
+#[tauri::command]
+async fn git_blame(path: String) -> Result<Vec<BlameLine>, String> {
+    let (repo, relative) = repo_for_file(Path::new(&path))?;
+
+    let index = repo.index().map_err(|e| e.to_string())?;
+    if index.get_path(&relative, 0).is_none() {
+        return Err("File is not tracked".to_string());
+    }
+
+    // Blame the working copy so edited lines show as uncommitted
+    let contents = fs::read(&path).map_err(|e| e.to_string())?;
+    let committed = repo
+        .blame_file(&relative, None)
+        .map_err(|e| e.to_string())?;
+    let blame = committed
+        .blame_buffer(&contents)
+        .map_err(|e| e.to_string())?;
+
+    let line_count = String::from_utf8_lossy(&contents).lines().count();
+
+    Ok((1..=line_count)
+        .map(|line| match blame.get_line(line) {
+            Some(hunk) if !hunk.final_commit_id().is_zero() => {
+                let signature = hunk.final_signature();
+                BlameLine {
+                    line,
+                    hash: short_hash(hunk.final_commit_id()),
+                    author: signature.name().unwrap_or("Unknown").to_string(),
+                    timestamp: signature.when().seconds(),
+                }
+            }
+            _ => BlameLine {
+                line,
+                hash: "0000000".to_string(),
+                author: "Not Committed Yet".to_string(),
+                timestamp: 0,
+            },
+        })
+        .collect())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiffLine {
+    // "+", "-" or " "
+    pub origin: String,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+fn head_tree(repo: &git2::Repository) -> Result<Option<git2::Tree<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head.peel_to_tree().map(Some).map_err(|e| e.to_string()),
+        // No commits yet
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+async fn git_diff_file(path: String) -> Result<Vec<DiffHunk>, String> {
+    let (repo, relative) = repo_for_file(Path::new(&path))?;
+    let tree = head_tree(&repo)?;
+
+    let mut options = git2::DiffOptions::new();
+    options
+        .pathspec(&relative)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+
+    let diff = repo
+        .diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut options))
+        .map_err(|e| e.to_string())?;
+
+    let mut hunks = Vec::new();
+    for delta in 0..diff.deltas().len() {
+        let Some(patch) = git2::Patch::from_diff(&diff, delta).map_err(|e| e.to_string())? else {
+            // Binary files have no textual hunks
+            continue;
+        };
+
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_index).map_err(|e| e.to_string())?;
+
+            let lines = (0..line_count)
+                .map(|line_index| {
+                    patch
+                        .line_in_hunk(hunk_index, line_index)
+                        .map(|line| DiffLine {
+                            origin: line.origin().to_string(),
+                            content: String::from_utf8_lossy(line.content())
+                                .trim_end_matches(['\r', '\n'])
+                                .to_string(),
+                            old_line: line.old_lineno(),
+                            new_line: line.new_lineno(),
+                        })
+                        .map_err(|e| e.to_string())
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+
+            hunks.push(DiffHunk {
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+    }
+
+    Ok(hunks)
+}
+
+#[tauri::command]
+async fn git_discard_changes(path: String, force: Option<bool>) -> Result<(), String> {
+    let (repo, relative) = repo_for_file(Path::new(&path))?;
+
+    let tree = head_tree(&repo)?.ok_or("Repository has no commits")?;
+    if tree.get_path(&relative).is_err() {
+        return Err("File has no committed version to restore".to_string());
+    }
+
+    let status = repo.status_file(&relative).map_err(|e| e.to_string())?;
+    let staged = status.intersects(
+        git2::Status::INDEX_NEW
+            | git2::Status::INDEX_MODIFIED
+            | git2::Status::INDEX_DELETED
+            | git2::Status::INDEX_RENAMED
+            | git2::Status::INDEX_TYPECHANGE,
+    );
+    if staged && !force.unwrap_or(false) {
+        return Err("File has staged changes; discard with force to lose them".to_string());
+    }
+
+    // Resets both the index entry and the working copy to HEAD
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force().path(&relative);
+    repo.checkout_head(Some(&mut checkout))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn git_list_branches(root: String) -> Result<Vec<String>, String> {
+    let Some(repo) = discover_repo(Path::new(&root))? else {
+        return Err("Not a git repository".to_string());
+    };
+
+    let mut branches: Vec<String> = repo
+        .branches(Some(git2::BranchType::Local))
+        .map_err(|e| e.to_string())?
+        .filter_map(|branch| branch.ok())
+        .filter_map(|(branch, _)| branch.name().ok().flatten().map(String::from))
+        .collect();
+
+    branches.sort();
+    Ok(branches)
+}
+
+fn has_uncommitted_changes(repo: &git2::Repository) -> Result<bool, String> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(false).include_ignored(false);
+
+    let statuses = repo
+        .statuses(Some(&mut options))
+        .map_err(|e| e.to_string())?;
+    Ok(statuses
+        .iter()
+        .any(|entry| entry.status() != git2::Status::CURRENT))
+}
+
+#[tauri::command]
+async fn git_checkout_branch(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    root: String,
+    branch: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let Some(repo) = discover_repo(Path::new(&root))? else {
+        return Err("Not a git repository".to_string());
+    };
+
+    let force = force.unwrap_or(false);
+    if !force && has_uncommitted_changes(&repo)? {
+        return Err(DIRTY_WORKTREE_ERROR.to_string());
+    }
+
+    let reference = repo
+        .find_branch(&branch, git2::BranchType::Local)
+        .map_err(|_| format!("No such branch: {}", branch))?
+        .into_reference();
+    let refname = reference.name().ok_or("Invalid branch name")?.to_string();
+    let target = reference.peel_to_commit().map_err(|e| e.to_string())?;
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+
+    repo.checkout_tree(target.as_object(), Some(&mut checkout))
+        .map_err(|e| e.to_string())?;
+    repo.set_head(&refname).map_err(|e| e.to_string())?;
+
+    let _ = app.emit_to(window.label(), "branch-changed", branch);
+    Ok(())
+}
+
+// Open the repository for a project and resolve `path` (absolute or relative
+// to the project root) within it. The file itself may no longer exist, so it's
+// resolved lexically.
+fn repo_path_in_project(root: &str, path: &str) -> Result<(git2::Repository, PathBuf), String> {
+    let root = Path::new(root).canonicalize().map_err(|e| e.to_string())?;
+    let repo = discover_repo(&root)?.ok_or("Not a git repository")?;
+    let workdir = repo
+        .workdir()
+        .ok_or("Repository has no working tree")?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+
+    let relative = root
+        .join(path)
+        .strip_prefix(&workdir)
+        .map(|p| p.to_path_buf())
+        .map_err(|_| "Path is outside the repository".to_string())?;
+    Ok((repo, relative))
+}
+
+#[tauri::command]
+async fn git_read_file_at_rev(root: String, path: String, rev: String) -> Result<String, String> {
+    let (repo, relative) = repo_path_in_project(&root, &path)?;
+
+    let tree = repo
+        .revparse_single(&rev)
+        .and_then(|object| object.peel_to_tree())
+        .map_err(|_| format!("Unknown revision: {}", rev))?;
+
+    let entry = tree
+        .get_path(&relative)
+        .map_err(|_| format!("{} did not exist at {}", relative.display(), rev))?;
+    let blob = entry
+        .to_object(&repo)
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|_| format!("{} is not a file at {}", relative.display(), rev))?;
+
+    Ok(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommitInfo {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    // Seconds since the Unix epoch
+    pub timestamp: i64,
+    pub summary: String,
+}
+
+fn blob_id_at(tree: &git2::Tree, path: &Path) -> Option<git2::Oid> {
+    tree.get_path(path).ok().map(|entry| entry.id())
+}
+
+#[tauri::command]
+async fn git_file_history(
+    root: String,
+    path: String,
+    limit: usize,
+) -> Result<Vec<CommitInfo>, String> {
+    let (repo, relative) = repo_path_in_project(&root, &path)?;
+
+    if head_tree(&repo)?.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk.push_head().map_err(|e| e.to_string())?;
+    revwalk
+        .set_sorting(git2::Sort::TIME)
+        .map_err(|e| e.to_string())?;
+
+    let mut history = Vec::new();
+    for oid in revwalk {
+        if history.len() >= limit {
+            break;
+        }
+
+        let commit = oid
+            .and_then(|oid| repo.find_commit(oid))
+            .map_err(|e| e.to_string())?;
+        let tree = commit.tree().map_err(|e| e.to_string())?;
+        let current = blob_id_at(&tree, &relative);
+
+        // A commit touched the file if its version differs from the first parent's
+        let previous = commit
+            .parent(0)
+            .ok()
+            .and_then(|parent| parent.tree().ok())
+            .and_then(|tree| blob_id_at(&tree, &relative));
+        if current == previous {
+            continue;
+        }
+
+        let author = commit.author();
+        history.push(CommitInfo {
+            hash: commit.id().to_string(),
+            short_hash: short_hash(commit.id()),
+            author: author.name().unwrap_or("Unknown").to_string(),
+            timestamp: author.when().seconds(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+        });
+    }
+
+    Ok(history)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CommandTiming {
+    pub command: String,
+    pub count: u64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+}
+
+#[tauri::command]
+fn get_command_timings(state: State<AppState>) -> Vec<CommandTiming> {
+    let timings = state.command_timings.lock().unwrap();
+
+    let mut report: Vec<CommandTiming> = timings
+        .iter()
+        .map(|(command, stats)| CommandTiming {
+            command: command.to_string(),
+            count: stats.count,
+            average_ms: stats.total.as_secs_f64() * 1000.0 / stats.count as f64,
+            max_ms: stats.max.as_secs_f64() * 1000.0,
+        })
+        .collect();
+
+    report.sort_by(|a, b| a.command.cmp(&b.command));
+    report
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirBatch {
+    pub batch_id: String,
+    pub entries: Vec<FileEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DirDone {
+    pub batch_id: String,
+    pub total: usize,
+    pub cancelled: bool,
+    pub error: Option<String>,
+}
+
+// Stream a huge directory as dir-batch events followed by dir-done. Entries
+// are never all held at once, so batches come in directory order; each batch
+// is sorted on its own.
+#[tauri::command]
+fn read_directory_stream(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    path: String,
+    batch_id: String,
+) -> Result<(), String> {
+    let read_dir = fs::read_dir(&path).map_err(|e| e.to_string())?;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state
+        .dir_reads
+        .lock()
+        .unwrap()
+        .insert(batch_id.clone(), cancelled.clone());
+
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut batch = Vec::with_capacity(DIR_BATCH_SIZE);
+        let mut total = 0;
+        let mut error = None;
+
+        let send = |entries: &mut Vec<FileEntry>| {
+            sort_entries(entries);
+            let _ = app.emit_to(
+                label.as_str(),
+                "dir-batch",
+                DirBatch {
+                    batch_id: batch_id.clone(),
+                    entries: std::mem::take(entries),
+                },
+            );
+        };
+
+        for entry in read_dir {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break;
+                }
+            };
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+
+            batch.push(FileEntry {
+                name,
+                path: entry.path().to_string_lossy().to_string(),
+                is_dir: entry.path().is_dir(),
+                is_symlink: entry.file_type().is_ok_and(|t| t.is_symlink()),
+                children: None,
+            });
+            total += 1;
+
+            if batch.len() >= DIR_BATCH_SIZE {
+                send(&mut batch);
+            }
+        }
+
+        let cancelled = cancelled.load(Ordering::Relaxed);
+        if !batch.is_empty() && !cancelled {
+            send(&mut batch);
+        }
+
+        app.state::<AppState>()
+            .dir_reads
+            .lock()
+            .unwrap()
+            .remove(&batch_id);
+        let _ = app.emit_to(
+            label.as_str(),
+            "dir-done",
+            DirDone {
+                batch_id,
+                total,
+                cancelled,
+                error,
+            },
+        );
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_dir_read(state: State<AppState>, batch_id: String) {
+    if let Some(cancelled) = state.dir_reads.lock().unwrap().get(&batch_id) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReadProgress {
+    pub read_id: String,
+    pub bytes_read: u64,
+    pub total: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReadComplete {
+    pub read_id: String,
+    pub contents: Option<String>,
+    pub error: Option<String>,
+}
+
+fn read_in_chunks(path: &Path, mut on_progress: impl FnMut(u64, u64)) -> Result<String, String> {
+    use std::io::Read;
+
+    let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let mut bytes = Vec::with_capacity(total as usize);
+    let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&chunk[..n]);
+        on_progress(bytes.len() as u64, total);
+    }
+
+    let contents = String::from_utf8(bytes).map_err(|e| e.to_string())?;
+    Ok(match contents.strip_prefix(UTF8_BOM) {
+        Some(stripped) => stripped.to_string(),
+        None => contents,
+    })
+}
+
+// Read a large text file on a background thread, reporting read-progress as
+// chunks arrive and finishing with read-complete
+#[tauri::command]
+fn read_file_with_progress(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    path: String,
+    read_id: String,
+) -> Result<(), String> {
+    if !Path::new(&path).is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let label = window.label().to_string();
+    std::thread::spawn(move || {
+        let result = read_in_chunks(Path::new(&path), |bytes_read, total| {
+            let _ = app.emit_to(
+                label.as_str(),
+                "read-progress",
+                ReadProgress {
+                    read_id: read_id.clone(),
+                    bytes_read,
+                    total,
+                },
+            );
+        });
+
+        let (contents, error) = match result {
+            Ok(contents) => (Some(contents), None),
+            Err(e) => (None, Some(e)),
+        };
+        let _ = app.emit_to(
+            label.as_str(),
+            "read-complete",
+            ReadComplete {
+                read_id,
+                contents,
+                error,
+            },
+        );
+    });
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RenameResult {
+    pub old_path: String,
+    pub new_path: Option<String>,
+    pub error: Option<String>,
+}
+
+// Find/replace within one file name (never its folder). `taken` holds names
+// already claimed earlier in the batch.
+fn rename_by_pattern(
+    from: &Path,
+    pattern: Option<&regex::Regex>,
+    find: &str,
+    replace: &str,
+    taken: &mut HashSet<PathBuf>,
+) -> Result<PathBuf, String> {
+    let name = from
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Path has no file name")?;
+
+    let new_name = match pattern {
+        Some(pattern) => pattern.replace_all(&name, replace).to_string(),
+        None => name.replace(find, replace),
+    };
+    if new_name == name {
+        return Err("Name does not match".to_string());
+    }
+    check_filename(&new_name)?;
+
+    let to = from.with_file_name(&new_name);
+    let case_only = is_safe_case_only_rename(from, &to);
+    if (!case_only && to.exists()) || taken.contains(&to) {
+        return Err(format!("Skipped: {} already exists", new_name));
+    }
+
+    if case_only {
+        rename_via_temp(from, &to)?;
+    } else {
+        fs::rename(from, &to).map_err(|e| e.to_string())?;
+    }
+
+    taken.insert(to.clone());
+    Ok(to)
+}
+
+// Files whose name doesn't change, or whose new name is taken, are skipped
+// and reported with an error rather than failing the whole batch
+#[tauri::command]
+async fn batch_rename(
+    paths: Vec<String>,
+    find: String,
+    replace: String,
+    regex: bool,
+) -> Result<Vec<RenameResult>, String> {
+    if find.is_empty() {
+        return Err("Find text cannot be empty".to_string());
+    }
+
+    let pattern = if regex {
+        Some(regex::Regex::new(&find).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+
+    Ok(paths
+        .into_iter()
+        .map(|old_path| {
+            match rename_by_pattern(
+                Path::new(&old_path),
+                pattern.as_ref(),
+                &find,
+                &replace,
+                &mut taken,
+            ) {
+                Ok(to) => RenameResult {
+                    old_path,
+                    new_path: Some(to.to_string_lossy().to_string()),
+                    error: None,
+                },
+                Err(e) => RenameResult {
+                    old_path,
+                    new_path: None,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect())
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+// 1-based numbers of lines whose indentation mixes tabs and spaces
+#[tauri::command]
+async fn detect_mixed_indentation(path: String) -> Result<Vec<usize>, String> {
+    let contents = read_text_file(Path::new(&path))?.contents;
+
+    Ok(contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let indent = leading_whitespace(line);
+            indent.contains(' ') && indent.contains('\t')
+        })
+        .map(|(idx, _)| idx + 1)
+        .collect())
+}
+
+// Write via a sibling temp file and rename so readers never see a partial file
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    check_not_directory(path)?;
+
+    // Write through symlinks, so the link survives and its target is updated
+    let path = match fs::canonicalize(path) {
+        Ok(resolved) => resolved,
+        Err(_) => path.to_path_buf(),
+    };
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Path has no file name")?;
+    let temp = path.with_file_name(format!(".{}.tmp", file_name));
+
+    fs::write(&temp, contents).map_err(|e| e.to_string())?;
+
+    // Keep the original's mode (e.g. the executable bit on scripts)
+    let result = match fs::metadata(&path) {
+        Ok(metadata) => fs::set_permissions(&temp, metadata.permissions()),
+        Err(_) => Ok(()),
+    }
+    .and_then(|_| fs::rename(&temp, &path));
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        e.to_string()
+    })
+}
+
+// Re-emit indentation of the same visual width using only tabs or only spaces.
+// Only leading whitespace changes, so trailing comments keep their columns.
+fn normalize_indent(indent: &str, use_tabs: bool, size: usize) -> String {
+    let width = indent.chars().fold(0, |col, c| match c {
+        '\t' => (col / size + 1) * size,
+        _ => col + 1,
+    });
+
+    if use_tabs {
+        format!("{}{}", "\t".repeat(width / size), " ".repeat(width % size))
+    } else {
+        " ".repeat(width)
+    }
+}
+
+#[tauri::command]
+async fn normalize_indentation(path: String, to: String, size: usize) -> Result<(), String> {
+    let use_tabs = match to.as_str() {
+        "tabs" => true,
+        "spaces" => false,
+        _ => return Err(format!("Unknown indentation style: {}", to)),
+    };
+    if size == 0 {
+        return Err("Indent size must be at least 1".to_string());
+    }
+
+    let file_path = Path::new(&path);
+    let file = read_text_file(file_path)?;
+
+    let mut output = String::with_capacity(file.contents.len());
+    if file.has_bom {
+        output.push_str(UTF8_BOM);
+    }
+
+    // split_inclusive keeps each line's original ending (\n or \r\n)
+    for line in file.contents.split_inclusive('\n') {
+        let indent = leading_whitespace(line);
+        if indent.len() == line.trim_end_matches(['\r', '\n']).len() {
+            // Whitespace-only lines are left alone
+            output.push_str(line);
+        } else {
+            output.push_str(&normalize_indent(indent, use_tabs, size));
+            output.push_str(&line[indent.len()..]);
+        }
+    }
+
+    if output == file.contents && !file.has_bom {
+        return Ok(());
+    }
+
+    write_atomic(file_path, output.as_bytes())
+}
+
+// An empty name reverts to the folder name. Every window showing the project
+// picks up the new name.
+#[tauri::command]
+fn set_project_display_name(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<AppState>,
+    name: String,
+) -> Result<(), String> {
+    let root = state
+        .project(window.label())
+        .path
+        .ok_or("No project is open")?;
+
+    let name = name.trim();
+    let value = (!name.is_empty()).then(|| serde_json::Value::String(name.to_string()));
+    write_project_setting(&app, &root, DISPLAY_NAME_SETTING, value)?;
+
+    let updated = project_with_display_name(&app, &root);
+    for project in state.projects.lock().unwrap().values_mut() {
+        if project.path.as_deref() == Some(root.as_str()) {
+            project.name = updated.name.clone();
+        }
+    }
+
+    Ok(())
+}
+
+fn generated_markers(app: &tauri::AppHandle) -> Vec<String> {
+    app.store(STORE_FILE)
+        .ok()
+        .and_then(|store| store.get(GENERATED_MARKERS_KEY))
+        .and_then(|value| serde_json::from_value::<Vec<String>>(value).ok())
+        .filter(|markers| !markers.is_empty())
+        .unwrap_or_else(|| {
+            DEFAULT_GENERATED_MARKERS
+                .iter()
+                .map(|m| m.to_string())
+                .collect()
+        })
+}
+
+#[tauri::command]
+async fn set_generated_markers(app: tauri::AppHandle, markers: Vec<String>) -> Result<(), String> {
+    let markers: Vec<String> = markers
+        .into_iter()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    if markers.is_empty() {
+        let _ = store.delete(GENERATED_MARKERS_KEY);
+    } else {
+        store.set(
+            GENERATED_MARKERS_KEY,
+            serde_json::to_value(&markers).unwrap(),
+        );
+    }
+    store.save().map_err(|e| e.to_string())
+}
+
+// Output folders only count below the project root, so a project that itself
+// lives under e.g. ~/dist isn't treated as generated wholesale
+fn is_generated_path(root: Option<&Path>, path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let in_output_dir = root
+        .and_then(|root| path.parent()?.strip_prefix(root).ok())
+        .is_some_and(|relative| {
+            relative.components().any(|c| {
+                matches!(
+                    c.as_os_str().to_string_lossy().as_ref(),
+                    "dist" | "generated" | "__generated__"
+                )
+            })
+        });
+
+    name.contains(".min.") || name.contains(".generated.") || in_output_dir
+}
+
+#[tauri::command]
+async fn is_generated_file(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<bool, String> {
+    let file_path = Path::new(&path);
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    let root = state.project(window.label()).path.map(PathBuf::from);
+    if is_generated_path(root.as_deref(), file_path) {
+        return Ok(true);
+    }
+
+    if is_binary_file(file_path) {
+        return Ok(false);
+    }
+
+    let Some(contents) = read_text_lossy(file_path) else {
+        return Ok(false);
+    };
+
+    let markers = generated_markers(&app);
+    let header_marked = contents
+        .lines()
+        .take(GENERATED_HEADER_LINES)
+        .any(|line| markers.iter().any(|marker| line.contains(marker.as_str())));
+    if header_marked {
+        return Ok(true);
+    }
+
+    // Minified bundles are a few enormous lines
+    let minifiable = file_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("js") || ext.eq_ignore_ascii_case("css"));
+    let line_count = contents.lines().count().max(1);
+    Ok(minifiable && contents.len() / line_count > MINIFIED_LINE_LENGTH)
+}
+
+// Show the folder holding settings.json in the OS file manager, returning its
+// path. The store plugin keeps its files in the app data directory.
+#[tauri::command]
+fn open_app_data_dir(app: tauri::AppHandle) -> Result<String, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let settings = dir.join(STORE_FILE);
+    if settings.is_file() {
+        app.opener()
+            .reveal_item_in_dir(&settings)
+            .map_err(|e| e.to_string())?;
+    } else {
+        app.opener()
+            .open_path(dir.to_string_lossy(), None::<&str>)
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(dir.to_string_lossy().to_string())
+}
+
+// Back up settings.json to settings.json.bak, then start over from defaults
+#[tauri::command]
+async fn reset_settings(app: tauri::AppHandle) -> Result<(), String> {
+    let store_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join(STORE_FILE);
+
+    if store_path.is_file() {
+        fs::copy(&store_path, store_path.with_extension("json.bak"))
+            .map_err(|e| format!("Failed to back up settings: {}", e))?;
+    }
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let mut changed: Vec<String> = store.keys();
+
+    store.clear();
+    store.set(
+        THEME_KEY,
+        serde_json::Value::String(DEFAULT_THEME.to_string()),
+    );
+    store.set(
+        EDITOR_FONT_KEY,
+        serde_json::to_value(EditorFont::default()).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())?;
+
+    changed.extend([THEME_KEY.to_string(), EDITOR_FONT_KEY.to_string()]);
+    changed.sort();
+    changed.dedup();
+    let _ = app.emit("settings-changed", changed);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StructureIssue {
+    // Relative to the project root
+    pub path: String,
+    // "missing" or "extra"
+    pub kind: String,
+    pub is_dir: bool,
+}
+
+fn collect_template_paths(template: &Path, dir: &Path, paths: &mut Vec<(PathBuf, bool)>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in read_dir.flatten() {
+        let entry_path = entry.path();
+        let Ok(relative) = entry_path.strip_prefix(template) else {
+            continue;
+        };
+        if relative == Path::new(TEMPLATE_MANIFEST) {
+            continue;
+        }
+
+        let is_dir = entry_path.is_dir();
+        paths.push((relative.to_path_buf(), is_dir));
+        if is_dir {
+            collect_template_paths(template, &entry_path, paths);
+        }
+    }
+}
+
+// Everything the template contains must exist in the project. Only top-level
+// project entries are checked for extras, since projects grow below that.
+#[tauri::command]
+async fn validate_project_structure(
+    app: tauri::AppHandle,
+    root: String,
+    template: String,
+) -> Result<Vec<StructureIssue>, String> {
+    check_filename(&template)?;
+    let template_path = templates_dir(&app)?.join(&template);
+    let root_path = Path::new(&root);
+
+    if !template_path.join(TEMPLATE_MANIFEST).is_file() {
+        return Err(format!("Template not found: {}", template));
+    }
+
+    if !root_path.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+
+    let mut expected = Vec::new();
+    collect_template_paths(&template_path, &template_path, &mut expected);
+    expected.sort();
+
+    let mut issues: Vec<StructureIssue> = expected
+        .iter()
+        .filter(|(relative, is_dir)| {
+            let actual = root_path.join(relative);
+            if *is_dir {
+                !actual.is_dir()
+            } else {
+                !actual.is_file()
+            }
+        })
+        .map(|(relative, is_dir)| StructureIssue {
+            path: relative.to_string_lossy().to_string(),
+            kind: "missing".to_string(),
+            is_dir: *is_dir,
+        })
+        .collect();
+
+    for entry in walk_entries(root_path)? {
+        if !expected
+            .iter()
+            .any(|(relative, _)| relative == Path::new(&entry.name))
+        {
+            issues.push(StructureIssue {
+                path: entry.name,
+                kind: "extra".to_string(),
+                is_dir: entry.is_dir,
+            });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct MergeReport {
+    pub copied: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+fn merge_into(
+    source: &Path,
+    dest: &Path,
+    policy: ConflictPolicy,
+    report: &mut MergeReport,
+) -> Result<(), String> {
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+
+    for entry in fs::read_dir(source).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path();
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        let mut target = dest.join(entry.file_name());
+
+        // Linked folders aren't followed, which could loop or escape the source
+        if file_type.is_symlink() && entry_path.is_dir() {
+            report.skipped += 1;
+            continue;
+        }
+        let is_dir = file_type.is_dir();
+
+        // Folders merge into a folder of the same name; any other collision is a conflict
+        let conflict = target.exists() && !(is_dir && target.is_dir());
+        if conflict {
+            match policy {
+                ConflictPolicy::Skip => {
+                    report.skipped += 1;
+                    continue;
+                }
+                ConflictPolicy::Overwrite => {
+                    if target.is_dir() {
+                        fs::remove_dir_all(&target).map_err(|e| e.to_string())?;
+                    } else {
+                        fs::remove_file(&target).map_err(|e| e.to_string())?;
+                    }
+                    report.overwritten += 1;
+                }
+                ConflictPolicy::Rename => {
+                    target = duplicate_name(&target)?;
+                    report.renamed += 1;
+                }
+            }
+        }
+
+        if is_dir {
+            merge_into(&entry_path, &target, policy, report)?;
+        } else {
+            fs::copy(&entry_path, &target).map_err(|e| e.to_string())?;
+            if !conflict {
+                report.copied += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Canonical form of a path that may not exist yet: its nearest existing
+// ancestor is canonicalized and the missing components appended
+fn canonicalize_nearest(path: &Path) -> Result<PathBuf, String> {
+    let absolute = std::path::absolute(path).map_err(|e| e.to_string())?;
+
+    let mut missing = Vec::new();
+    let mut existing = absolute.as_path();
+    while !existing.exists() {
+        missing.push(existing.file_name().unwrap_or_default().to_os_string());
+        existing = existing
+            .parent()
+            .ok_or_else(|| format!("No existing parent for {}", path.display()))?;
+    }
+
+    let mut resolved = existing.canonicalize().map_err(|e| e.to_string())?;
+    resolved.extend(missing.iter().rev());
+    Ok(resolved)
+}
+
+#[tauri::command]
+async fn merge_directories(
+    source: String,
+    dest: String,
+    on_conflict: String,
+) -> Result<MergeReport, String> {
+    let policy = match on_conflict.as_str() {
+        "skip" => ConflictPolicy::Skip,
+        "overwrite" => ConflictPolicy::Overwrite,
+        "rename" => ConflictPolicy::Rename,
+        _ => return Err(format!("Unknown conflict policy: {}", on_conflict)),
+    };
+
+    let source = Path::new(&source);
+    let dest = Path::new(&dest);
+
+    if !source.is_dir() {
+        return Err("Source is not a directory".to_string());
+    }
+
+    if dest.exists() && !dest.is_dir() {
+        return Err("Destination is not a directory".to_string());
+    }
+
+    let source_real = source.canonicalize().map_err(|e| e.to_string())?;
+    if canonicalize_nearest(dest)?.starts_with(&source_real) {
+        return Err("Cannot merge a folder into itself".to_string());
+    }
+
+    let mut report = MergeReport::default();
+    merge_into(source, dest, policy, &mut report)?;
+    Ok(report)
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Per-file digests of path and contents. Only EXCLUDED_DIRS are skipped, so
+// hidden files count too.
+fn checksum_files(root: &Path, dir: &Path, digests: &mut Vec<String>) -> Result<(), String> {
+    use sha2::{Digest, Sha256};
+
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let entry_path = entry.path();
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+
+        if file_type.is_dir() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !EXCLUDED_DIRS.contains(&name.as_str()) {
+                checksum_files(root, &entry_path, digests)?;
+            }
+        } else if entry_path.is_file() {
+            // Forward slashes so the same tree hashes identically on every OS
+            let relative = entry_path
+                .strip_prefix(root)
+                .map_err(|e| e.to_string())?
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let mut hasher = Sha256::new();
+            hasher.update(relative.as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(&entry_path).map_err(|e| e.to_string())?);
+            digests.push(hex_digest(&hasher.finalize()));
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn tree_checksum(root: String) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+
+    let root = Path::new(&root);
+    if !root.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut digests = Vec::new();
+    checksum_files(root, root, &mut digests)?;
+
+    // Sorting makes the result independent of directory listing order
+    digests.sort();
+    let mut hasher = Sha256::new();
+    for digest in &digests {
+        hasher.update(digest.as_bytes());
+    }
+    Ok(hex_digest(&hasher.finalize()))
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ZipProgress {
+    pub dest_zip: String,
+    pub written: usize,
+    pub total: usize,
+}
+
+fn collect_export_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let entry_path = entry.path();
+
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            if name != ".git" && !EXCLUDED_DIRS.contains(&name.as_str()) {
+                collect_export_files(&entry_path, files)?;
+            }
+        } else if entry_path.is_file() {
+            files.push(entry_path);
+        }
+    }
+
+    Ok(())
+}
+
+// Project files for an export, relative to `root`. Unlike project walks this
+// keeps hidden files such as .retro-ide/, but never .git or EXCLUDED_DIRS;
+// git-ignored files are left out unless asked for.
+fn export_files(root: &Path, include_ignored: bool) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    collect_export_files(root, &mut files)?;
+
+    let repo = if include_ignored {
+        None
+    } else {
+        discover_repo(root)?
+    };
+    let workdir = repo
+        .as_ref()
+        .and_then(|repo| repo.workdir())
+        .and_then(|dir| dir.canonicalize().ok());
+    let root_real = root.canonicalize().map_err(|e| e.to_string())?;
+
+    let mut relative_files: Vec<PathBuf> = files
+        .into_iter()
+        .filter_map(|file| file.strip_prefix(root).ok().map(|p| p.to_path_buf()))
+        .filter(|relative| match (&repo, &workdir) {
+            (Some(repo), Some(workdir)) => root_real
+                .join(relative)
+                .strip_prefix(workdir)
+                .map(|in_repo| !repo.is_path_ignored(in_repo).unwrap_or(false))
+                .unwrap_or(true),
+            _ => true,
+        })
+        .collect();
+
+    relative_files.sort();
+    Ok(relative_files)
+}
+
+#[tauri::command]
+async fn export_project_zip(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    root: String,
+    dest_zip: String,
+    include_ignored: bool,
+) -> Result<(), String> {
+    use std::io::Write;
+
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Project folder does not exist".to_string());
+    }
+
+    let dest_path = Path::new(&dest_zip);
+    if dest_path.starts_with(root_path) {
+        return Err("Cannot export a project into itself".to_string());
+    }
+
+    let files = export_files(root_path, include_ignored)?;
+    let top = root_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "project".to_string());
+
+    let file = fs::File::create(dest_path).map_err(|e| e.to_string())?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (idx, relative) in files.iter().enumerate() {
+        // Entries live under the project's folder name, with forward slashes
+        let name = std::iter::once(top.clone())
+            .chain(
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string()),
+            )
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let contents = fs::read(root_path.join(relative)).map_err(|e| e.to_string())?;
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        zip.write_all(&contents).map_err(|e| e.to_string())?;
+
+        if (idx + 1) % ZIP_PROGRESS_INTERVAL == 0 || idx + 1 == files.len() {
+            let _ = app.emit_to(
+                window.label(),
+                "zip-progress",
+                ZipProgress {
+                    dest_zip: dest_zip.clone(),
+                    written: idx + 1,
+                    total: files.len(),
+                },
+            );
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Extract an archive into `dest_dir` and open it. An archive whose entries
+// share one top-level folder (as export_project_zip writes) opens that folder.
+#[tauri::command]
+async fn import_project_zip(
+    app: tauri::AppHandle,
+    window: tauri::Window,
+    zip_path: String,
+    dest_dir: String,
+) -> Result<ProjectState, String> {
+    let dest = Path::new(&dest_dir);
+    let file = fs::File::open(&zip_path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    // Validate every entry before writing anything, guarding against zip-slip
+    let mut entries = Vec::with_capacity(archive.len());
+    for idx in 0..archive.len() {
+        let entry = archive.by_index(idx).map_err(|e| e.to_string())?;
+        let relative = entry
+            .enclosed_name()
+            .filter(|name| dest.join(name).starts_with(dest))
+            .ok_or_else(|| format!("Archive entry escapes destination: {}", entry.name()))?;
+
+        let target = dest.join(&relative);
+        if target.is_file() {
+            return Err(format!("{} already exists", target.display()));
+        }
+        entries.push((idx, relative, entry.is_dir()));
+    }
+
+    for (idx, relative, is_dir) in &entries {
+        let target = dest.join(relative);
+        if *is_dir {
+            fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut entry = archive.by_index(*idx).map_err(|e| e.to_string())?;
+        let mut out = fs::File::create(&target).map_err(|e| e.to_string())?;
+        std::io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+    }
+
+    let tops: HashSet<_> = entries
+        .iter()
+        .filter_map(|(_, relative, _)| relative.components().next())
+        .collect();
+    let root = match tops.into_iter().collect::<Vec<_>>().as_slice() {
+        [top] if dest.join(top).is_dir() => dest.join(top),
+        _ => dest.to_path_buf(),
+    };
+
+    let path_str = root.to_string_lossy().to_string();
+    let project = project_with_display_name(&app, &path_str);
+
+    if let Ok(store) = app.store(STORE_FILE) {
+        store.set(LAST_PROJECT_KEY, serde_json::to_value(&path_str).unwrap());
+        let _ = store.save();
+    }
+    activate_project(&app, window.label(), project.clone());
+
+    Ok(project)
+}
+
+// Expand $VAR and ${VAR} references, plus %VAR% on Windows. Anything that
+// isn't a set variable is kept literally, since `$` and `%` are legal in paths.
+fn expand_env_vars(input: &str) -> String {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let markers: &[char] = if cfg!(windows) { &['$', '%'] } else { &['$'] };
+
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(idx) = rest.find(markers) {
+        out.push_str(&rest[..idx]);
+        let marker = &rest[idx..];
+
+        // (variable name, text it replaces)
+        let reference = if let Some(braced) = marker.strip_prefix("${") {
+            braced
+                .find('}')
+                .map(|end| (&braced[..end], &marker[..end + 3]))
+        } else if let Some(after) = marker.strip_prefix('$') {
+            let len = after.len() - after.trim_start_matches(is_name_char).len();
+            Some((&after[..len], &marker[..len + 1]))
+        } else {
+            let after = &marker[1..];
+            after
+                .find('%')
+                .map(|end| (&after[..end], &marker[..end + 2]))
+        };
+
+        match reference {
+            Some((name, text)) if !name.is_empty() && name.chars().all(is_name_char) => {
+                match std::env::var(name) {
+                    Ok(value) => out.push_str(&value),
+                    Err(_) => out.push_str(text),
+                }
+                rest = &marker[text.len()..];
+            }
+            _ => {
+                out.push_str(&marker[..1]);
+                rest = &marker[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[tauri::command]
+fn expand_path(app: tauri::AppHandle, input: String) -> Result<String, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err("Path cannot be empty".to_string());
+    }
+
+    let expanded = expand_env_vars(input);
+
+    // `~` alone or followed by a separator means the home directory
+    let path = match expanded.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let home = app.path().home_dir().map_err(|e| e.to_string())?;
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(expanded),
+    };
+
+    std::path::absolute(&path)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResourceStats {
+    pub projects: usize,
+    pub project_watchers: usize,
+    pub git_watchers: usize,
+    pub settings_watcher: bool,
+    pub cached_directories: usize,
+    pub cached_directory_entries: usize,
+    pub file_indexes: usize,
+    pub highlight_cache_entries: usize,
+    pub file_locks: usize,
+    pub running_tasks: usize,
+}
+
+// Counts of what AppState is holding on to, for spotting leaked watchers
+#[tauri::command]
+fn get_resource_stats(state: State<AppState>) -> ResourceStats {
+    let dir_cache = state.dir_cache.lock().unwrap();
+
+    ResourceStats {
+        projects: state
+            .projects
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|project| project.path.is_some())
+            .count(),
+        project_watchers: state.watchers.lock().unwrap().len(),
+        git_watchers: state.git_watchers.lock().unwrap().len(),
+        settings_watcher: state.settings_watcher.lock().unwrap().is_some(),
+        cached_directories: dir_cache.len(),
+        cached_directory_entries: dir_cache.values().map(|entries| entries.len()).sum(),
+        file_indexes: state.file_indexes.lock().unwrap().len(),
+        highlight_cache_entries: state.highlight_cache.lock().unwrap().len(),
+        file_locks: state.file_locks.lock().unwrap().len(),
+        running_tasks: state.tasks.lock().unwrap().len(),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MountPoint {
+    pub path: String,
+    pub label: String,
+    pub file_system: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub removable: bool,
+}
+
+// Roots for the in-app file browser. Platforms sysinfo can't list disks on
+// simply yield no entries.
+#[tauri::command]
+async fn list_mount_points() -> Result<Vec<MountPoint>, String> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    let mut mounts: Vec<MountPoint> = disks
+        .iter()
+        .map(|disk| MountPoint {
+            path: disk.mount_point().to_string_lossy().to_string(),
+            label: disk.name().to_string_lossy().to_string(),
+            file_system: disk.file_system().to_string_lossy().to_string(),
+            available_bytes: disk.available_space(),
+            total_bytes: disk.total_space(),
+            removable: disk.is_removable(),
+        })
+        .collect();
+
+    mounts.sort_by(|a, b| a.path.cmp(&b.path));
+    mounts.dedup_by(|a, b| a.path == b.path);
+    Ok(mounts)
+}
+
+// Absolute target of a symlink. A dangling link fails with BROKEN_SYMLINK_ERROR
+// followed by the unresolved target, so the UI can badge it.
+#[tauri::command]
+async fn resolve_symlink(path: String) -> Result<String, String> {
+    let link = Path::new(&path);
+
+    let is_link = fs::symlink_metadata(link)
+        .map_err(|e| e.to_string())?
+        .file_type()
+        .is_symlink();
+    if !is_link {
+        return Err("Path is not a symbolic link".to_string());
+    }
+
+    let target = fs::read_link(link).map_err(|e| e.to_string())?;
+    // Relative targets are relative to the link's own folder
+    let target = match link.parent() {
+        Some(parent) if target.is_relative() => parent.join(target),
+        _ => target,
+    };
+
+    target
+        .canonicalize()
+        .map(|resolved| resolved.to_string_lossy().to_string())
+        .map_err(|_| format!("{}{}", BROKEN_SYMLINK_ERROR, target.display()))
+}
+
+#[tauri::command]
+async fn create_symlink(target: String, link_path: String) -> Result<(), String> {
+    let link = Path::new(&link_path);
+    let target_path = Path::new(&target);
+
+    if fs::symlink_metadata(link).is_ok() {
+        return Err("A file or folder with that name already exists".to_string());
+    }
+
+    if let Some(name) = link.file_name() {
+        check_filename(&name.to_string_lossy())?;
+    }
+
+    // The target is stored as given; a relative one is resolved from the link's folder
+    let resolved = match link.parent() {
+        Some(parent) if target_path.is_relative() => parent.join(target_path),
+        _ => target_path.to_path_buf(),
+    };
+    if !resolved.exists() {
+        return Err("Link target does not exist".to_string());
+    }
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(target_path, link);
+
+    #[cfg(windows)]
+    let result = if resolved.is_dir() {
+        std::os::windows::fs::symlink_dir(target_path, link)
+    } else {
+        std::os::windows::fs::symlink_file(target_path, link)
+    };
+
+    #[cfg(not(any(unix, windows)))]
+    let result: std::io::Result<()> = Err(std::io::ErrorKind::Unsupported.into());
+
+    result.map_err(|e| {
+        // ERROR_PRIVILEGE_NOT_HELD: Windows needs Developer Mode or admin rights
+        if cfg!(windows) && e.raw_os_error() == Some(1314) {
+            "Creating symbolic links requires Developer Mode or administrator rights".to_string()
+        } else {
+            e.to_string()
+        }
+    })
+}
+
+fn encode_png(image: &image::DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+// Cached thumbnails are keyed by path, modification time, file size and
+// thumbnail size, so an edited image never serves a stale thumbnail
+fn thumbnail_cache_path(
+    app: &tauri::AppHandle,
+    path: &Path,
+    max_dim: u32,
+) -> Result<PathBuf, String> {
+    use sha2::{Digest, Sha256};
+
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let modified = metadata
+        .modified()
+        .map_err(|e| e.to_string())?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update([0]);
+    hasher.update(modified.as_nanos().to_le_bytes());
+    hasher.update(metadata.len().to_le_bytes());
+    hasher.update(max_dim.to_le_bytes());
+
+    app.path()
+        .app_cache_dir()
+        .map(|dir| {
+            dir.join(THUMBNAILS_DIR)
+                .join(format!("{}.png", hex_digest(&hasher.finalize()[..16])))
+        })
+        .map_err(|e| e.to_string())
+}
+
+// Thumbnails of images that changed or haven't been viewed in a while would
+// otherwise pile up forever
+fn prune_thumbnail_cache(app: &tauri::AppHandle) -> Result<usize, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join(THUMBNAILS_DIR);
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+
+    let max_age = std::time::Duration::from_secs(THUMBNAIL_MAX_AGE_DAYS * 24 * 60 * 60);
+    let now = std::time::SystemTime::now();
+
+    let mut pruned = 0;
+    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+
+        if expired && fs::remove_file(entry.path()).is_ok() {
+            pruned += 1;
+        }
+    }
+
+    Ok(pruned)
+}
+
+#[tauri::command]
+async fn get_thumbnail(
+    app: tauri::AppHandle,
+    path: String,
+    max_dim: u32,
+) -> Result<FileData, String> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let file_path = Path::new(&path);
+
+    if !file_path.is_file() {
+        return Err("Path is not a file".to_string());
+    }
+
+    if max_dim == 0 {
+        return Err("Thumbnail size must be at least 1".to_string());
+    }
+
+    let cache_path = thumbnail_cache_path(&app, file_path, max_dim)?;
+
+    let bytes = match fs::read(&cache_path) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            let image = image::open(file_path).map_err(|e| e.to_string())?;
+
+            // Small images are sent as they are rather than scaled up
+            let thumbnail = if image.width() > max_dim || image.height() > max_dim {
+                image.thumbnail(max_dim, max_dim)
+            } else {
+                image
+            };
+
+            let bytes = encode_png(&thumbnail)?;
+            if let Some(parent) = cache_path.parent() {
+                let _ = fs::create_dir_all(parent).and_then(|_| fs::write(&cache_path, &bytes));
+            }
+            bytes
+        }
+    };
+
+    Ok(FileData {
+        data: STANDARD.encode(&bytes),
+        mime_type: "image/png".to_string(),
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub color_type: String,
+    pub format: String,
+}
+
+// Only the header is decoded, so this stays cheap for large images
+#[tauri::command]
+async fn get_image_info(path: String) -> Result<ImageInfo, String> {
+    use image::ImageDecoder;
+
+    let reader = image::ImageReader::open(&path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?;
+    let format = reader.format().ok_or("Not a recognized image format")?;
+    let decoder = reader.into_decoder().map_err(|e| e.to_string())?;
+    let (width, height) = decoder.dimensions();
+
+    Ok(ImageInfo {
+        width,
+        height,
+        color_type: format!("{:?}", decoder.color_type()),
+        format: format!("{:?}", format).to_uppercase(),
+    })
+}
+
+#[tauri::command]
+async fn convert_image(src: String, dest: String, format: String) -> Result<(), String> {
+    let format = image::ImageFormat::from_extension(format.trim().to_lowercase())
+        .filter(|format| format.writing_enabled())
+        .ok_or_else(|| format!("Unsupported image format: {}", format))?;
+
+    let src_path = Path::new(&src);
+    let dest_path = Path::new(&dest);
+
+    if !src_path.is_file() {
+        return Err("Source is not a file".to_string());
+    }
+
+    if src_path == dest_path {
+        return Err("Destination must differ from the source".to_string());
+    }
+    check_not_directory(dest_path)?;
+
+    let image = image::open(src_path).map_err(|e| e.to_string())?;
+
+    // JPEG has no alpha channel, so flatten before encoding
+    let image = match format {
+        image::ImageFormat::Jpeg => image::DynamicImage::ImageRgb8(image.to_rgb8()),
+        _ => image,
+    };
+
+    image
+        .save_with_format(dest_path, format)
+        .map_err(|e| format!("Cannot convert to {:?}: {}", format, e))
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+const MAX_PALETTE_SIZE: usize = 256;
+
+// Reads the PLTE chunk (and tRNS alpha, if present) of an indexed PNG
+fn indexed_png_palette(path: &Path) -> Option<Vec<Color>> {
+    let file = fs::File::open(path).ok()?;
+    let reader = png::Decoder::new(file).read_info().ok()?;
+    let info = reader.info();
+
+    if info.color_type != png::ColorType::Indexed {
+        return None;
+    }
+
+    let palette = info.palette.as_ref()?;
+    let alphas = info.trns.as_deref().unwrap_or(&[]);
+
+    Some(
+        palette
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(i, rgb)| Color {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+                a: alphas.get(i).copied().unwrap_or(255),
+            })
+            .collect(),
+    )
+}
+
+#[tauri::command]
+async fn extract_palette(path: String) -> Result<Vec<Color>, String> {
+    let path = Path::new(&path);
+
+    if let Some(palette) = indexed_png_palette(path) {
+        return Ok(palette);
+    }
+
+    let image = image::open(path)
+        .map_err(|e| format!("Not a decodable image: {}", e))?
+        .to_rgba8();
+
+    // Images that already fit in a palette keep their exact colors
+    let mut colors: Vec<Color> = Vec::new();
+    let mut seen = HashSet::new();
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let color = Color { r, g, b, a };
+        if seen.insert(color.clone()) {
+            colors.push(color);
+            if colors.len() > MAX_PALETTE_SIZE {
+                break;
+            }
+        }
+    }
+
+    if colors.len() <= MAX_PALETTE_SIZE {
+        return Ok(colors);
+    }
+
+    let quantizer = color_quant::NeuQuant::new(10, MAX_PALETTE_SIZE, image.as_raw());
+    Ok(quantizer
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|rgba| Color {
+            r: rgba[0],
+            g: rgba[1],
+            b: rgba[2],
+            a: rgba[3],
+        })
+        .collect())
+}
+
+// Groups of files with identical contents. Only files sharing a size with
+// another file are hashed; empty files are ignored.
+#[tauri::command]
+async fn find_duplicate_files(root: String) -> Result<Vec<Vec<String>>, String> {
+    use sha2::{Digest, Sha256};
+
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for file in files {
+        let size = fs::metadata(&file).map_err(|e| e.to_string())?.len();
+        if size > 0 {
+            by_size.entry(size).or_default().push(file);
+        }
+    }
+
+    let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    for candidates in by_size.into_values().filter(|paths| paths.len() > 1) {
+        for file in candidates {
+            let mut hasher = Sha256::new();
+            let mut reader = fs::File::open(&file).map_err(|e| e.to_string())?;
+            std::io::copy(&mut reader, &mut hasher).map_err(|e| e.to_string())?;
+            by_hash
+                .entry(hex_digest(&hasher.finalize()))
+                .or_default()
+                .push(file.to_string_lossy().to_string());
+        }
+    }
+
+    let mut groups: Vec<Vec<String>> = by_hash
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|mut paths| {
+            paths.sort();
+            paths
+        })
+        .collect();
+    groups.sort();
+
+    Ok(groups)
+}
+
+fn normalize_extensions(extensions: &[String]) -> HashSet<String> {
+    extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+fn has_extension(path: &Path, extensions: &HashSet<String>) -> bool {
+    path.extension()
+        .map(|e| extensions.contains(&e.to_string_lossy().to_lowercase()))
+        .unwrap_or(false)
+}
+
+// Heuristic: an asset counts as referenced when its file name appears
+// verbatim anywhere in a source file
+#[tauri::command]
+async fn find_unreferenced_files(
+    root: String,
+    source_extensions: Vec<String>,
+    asset_extensions: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let source_extensions = normalize_extensions(&source_extensions);
+    let asset_extensions = normalize_extensions(&asset_extensions);
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+
+    let mut sources = Vec::new();
+    for file in files
+        .iter()
+        .filter(|f| has_extension(f, &source_extensions))
+    {
+        let bytes = fs::read(file).map_err(|e| e.to_string())?;
+        sources.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    let mut unreferenced: Vec<String> = files
+        .iter()
+        .filter(|f| has_extension(f, &asset_extensions))
+        .filter(|asset| {
+            let name = asset
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            !sources.iter().any(|source| source.contains(&name))
+        })
+        .map(|asset| asset.to_string_lossy().to_string())
+        .collect();
+    unreferenced.sort();
+
+    Ok(unreferenced)
+}
+
+// Nearest ancestor carrying a project marker: .git, .retro-ide or its manifest
+#[tauri::command]
+async fn find_project_root(file_path: String) -> Result<Option<String>, String> {
+    let path = Path::new(&file_path);
+    if !path.exists() {
+        return Err(format!("Path does not exist: {}", file_path));
+    }
+
+    let path = fs::canonicalize(path).map_err(|e| e.to_string())?;
+    let start = if path.is_dir() {
+        path.as_path()
+    } else {
+        match path.parent() {
+            Some(parent) => parent,
+            None => return Ok(None),
+        }
+    };
+
+    // .git is a file rather than a folder inside worktrees and submodules
+    Ok(start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists() || dir.join(PROJECT_CONFIG_DIR).is_dir())
+        .map(|dir| dir.to_string_lossy().to_string()))
+}
+
+// A dropped folder becomes the window's project; dropped files are handed to
+// the frontend to open as tabs
+fn handle_dropped_paths(window: &tauri::Window, paths: &[PathBuf]) {
+    let app = window.app_handle();
+
+    if let Some(folder) = paths.iter().find(|path| path.is_dir()) {
+        let path_str = folder.to_string_lossy().to_string();
+        let project = project_with_display_name(app, &path_str);
+
+        if let Ok(store) = app.store(STORE_FILE) {
+            store.set(LAST_PROJECT_KEY, serde_json::to_value(&path_str).unwrap());
+            let _ = store.save();
+        }
+
+        activate_project(app, window.label(), project.clone());
+        let _ = app.emit_to(window.label(), "project-opened", project);
+    }
+
+    let files: Vec<FileEntry> = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .map(|path| FileEntry {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().to_string(),
+            is_dir: false,
+            is_symlink: path.is_symlink(),
+            children: None,
+        })
+        .collect();
+
+    if !files.is_empty() {
+        let _ = app.emit_to(window.label(), "files-dropped", files);
+    }
+}
+
+// Identifier-like words in a line: letters, digits and underscores, not
+// starting with a digit
+fn line_identifiers(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.chars().next().is_some_and(|c| !c.is_ascii_digit()))
+}
+
+// `prefix` is expected to be lowercase already
+fn matches_completion_prefix(word: &str, prefix: &str) -> bool {
+    word.len() > prefix.len() && word.to_lowercase().starts_with(prefix)
+}
+
+// Words in the file starting with `prefix` (case-insensitively), most frequent
+// first. When the cursor `line` is known, ties go to words used nearer to it.
+#[tauri::command]
+async fn get_word_completions(
+    path: String,
+    prefix: String,
+    limit: usize,
+    line: Option<usize>,
+) -> Result<Vec<String>, String> {
+    if prefix.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let prefix = prefix.to_lowercase();
+    let contents = fs::read(&path).map_err(|e| e.to_string())?;
+    let contents = String::from_utf8_lossy(&contents);
+
+    // word -> (occurrences, distance in lines to the cursor)
+    let mut candidates: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (idx, text) in contents.lines().enumerate() {
+        let distance = line.map_or(0, |line| (idx + 1).abs_diff(line));
+        for word in line_identifiers(text).filter(|w| matches_completion_prefix(w, &prefix)) {
+            let entry = candidates.entry(word).or_insert((0, distance));
+            entry.0 += 1;
+            entry.1 = entry.1.min(distance);
+        }
+    }
+
+    let mut ranked: Vec<(&str, (usize, usize))> = candidates.into_iter().collect();
+    ranked.sort_by(|(a, (a_count, a_distance)), (b, (b_count, b_distance))| {
+        b_count
+            .cmp(a_count)
+            .then_with(|| a_distance.cmp(b_distance))
+            .then_with(|| a.cmp(b))
+    });
+
+    Ok(ranked
+        .into_iter()
+        .take(limit)
+        .map(|(word, _)| word.to_string())
+        .collect())
+}
+
+// Completions drawn from the current project's cached index. Until warm_index
+// has finished there is no index, and so nothing to suggest.
+#[tauri::command]
+fn get_project_completions(
+    window: tauri::Window,
+    state: State<AppState>,
+    prefix: String,
+    limit: usize,
+) -> Result<Vec<String>, String> {
+    let _timer = state.time_command("get_project_completions");
+    let root = current_project_root(&state, window.label())?;
+
+    if prefix.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    let prefix = prefix.to_lowercase();
+    let mut indexes = state.file_indexes.lock().unwrap();
+    let Some(index) = indexes.get_mut(&root) else {
+        return Ok(Vec::new());
+    };
+
+    let mut ranked: Vec<(&String, &usize)> = index
+        .identifier_counts()
+        .iter()
+        .filter(|(word, _)| matches_completion_prefix(word, &prefix))
+        .collect();
+    ranked.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+
+    Ok(ranked
+        .into_iter()
+        .take(limit)
+        .map(|(word, _)| word.clone())
+        .collect())
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Whether `line[start..end]` stands alone rather than inside a larger identifier
+fn is_whole_word(line: &str, start: usize, end: usize) -> bool {
+    !line[..start]
+        .chars()
+        .next_back()
+        .is_some_and(is_identifier_char)
+        && !line[end..].chars().next().is_some_and(is_identifier_char)
+}
+
+fn search_symbol(
+    path: &Path,
+    contents: &str,
+    symbol: &str,
+    whole_word: bool,
+    results: &mut Vec<SearchMatch>,
+) {
+    for (idx, line) in contents.lines().enumerate() {
+        for (byte_idx, _) in line.match_indices(symbol) {
+            if results.len() >= MAX_SEARCH_RESULTS {
+                return;
+            }
+            if whole_word && !is_whole_word(line, byte_idx, byte_idx + symbol.len()) {
+                continue;
+            }
+
+            results.push(SearchMatch {
+                path: path.to_string_lossy().to_string(),
+                line: idx + 1,
+                column: line[..byte_idx].chars().count() + 1,
+                text: line.to_string(),
+            });
+        }
+    }
+}
+
+// Case-sensitive occurrences of `symbol`, optionally only where it isn't part
+// of a longer identifier
+#[tauri::command]
+async fn find_references(
+    state: State<'_, AppState>,
+    root: String,
+    symbol: String,
+    whole_word: bool,
+) -> Result<Vec<SearchMatch>, String> {
+    let _timer = state.time_command("find_references");
+    let root_path = Path::new(&root);
+
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let mut results = Vec::new();
+    let symbol = symbol.trim();
+    if symbol.is_empty() {
+        return Ok(results);
+    }
+
+    let indexes = state.file_indexes.lock().unwrap();
+    if let Some(index) = indexes.get(root_path) {
+        for (path, contents) in &index.files {
+            match contents {
+                Some(contents) => search_symbol(path, contents, symbol, whole_word, &mut results),
+                None => {
+                    if let Some(contents) = read_text_lossy(path) {
+                        search_symbol(path, &contents, symbol, whole_word, &mut results);
+                    }
+                }
+            }
+        }
+        return Ok(results);
+    }
+    drop(indexes);
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    for file in files {
+        if results.len() >= MAX_SEARCH_RESULTS {
+            break;
+        }
+        if is_binary_file(&file) {
+            continue;
+        }
+        if let Some(contents) = read_text_lossy(&file) {
+            search_symbol(&file, &contents, symbol, whole_word, &mut results);
+        }
+    }
+
+    Ok(results)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReplaceResult {
+    pub path: String,
+    pub replacements: usize,
+    // 1-based lines that were (or, in a dry run, would be) changed
+    pub lines: Vec<usize>,
+    // Set when the file couldn't be written; it is left unchanged
+    pub error: Option<String>,
+}
+
+// Byte ranges of a line holding code: outside "double-quoted" strings and
+// before any line comment. Best effort, as block comments aren't tracked.
+fn code_spans(line: &str, comment_prefixes: &[&'static str]) -> Vec<std::ops::Range<usize>> {
+    let mut spans = Vec::new();
+    let mut start = Some(0);
+    let mut previous: Option<char> = None;
+
+    for (idx, c) in line.char_indices() {
+        if c == '"' {
+            match start.take() {
+                Some(code_start) => spans.push(code_start..idx),
+                None => start = Some(idx + 1),
+            }
+        } else if let Some(code_start) = start {
+            // Keyword comments such as BASIC's REM must start a word
+            let starts_word = !previous.is_some_and(is_identifier_char);
+            if let Some(prefix) = find_comment_prefix(&line[idx..], comment_prefixes) {
+                if starts_word || !prefix.starts_with(is_identifier_char) {
+                    spans.push(code_start..idx);
+                    return spans;
+                }
+            }
+        }
+        previous = Some(c);
+    }
+
+    if let Some(code_start) = start {
+        spans.push(code_start..line.len());
+    }
+    spans
+}
+
+// Rewrite whole-word occurrences of `old` in code, leaving strings and
+// comments alone. Returns the new contents and the 1-based changed lines.
+fn rename_in_source(
+    contents: &str,
+    old: &str,
+    new: &str,
+    comment_prefixes: &[&'static str],
+) -> (String, usize, Vec<usize>) {
+    let mut renamed = String::with_capacity(contents.len());
+    let mut replacements = 0;
+    let mut lines = Vec::new();
+
+    for (idx, line) in contents.split_inclusive('\n').enumerate() {
+        let spans = code_spans(line, comment_prefixes);
+        let mut last = 0;
+        let mut changed = false;
+
+        for (byte_idx, _) in line.match_indices(old) {
+            let end = byte_idx + old.len();
+            let in_code = spans
+                .iter()
+                .any(|span| span.start <= byte_idx && end <= span.end);
+            if !in_code || !is_whole_word(line, byte_idx, end) {
+                continue;
+            }
+
+            renamed.push_str(&line[last..byte_idx]);
+            renamed.push_str(new);
+            last = end;
+            replacements += 1;
+            changed = true;
+        }
+
+        renamed.push_str(&line[last..]);
+        if changed {
+            lines.push(idx + 1);
+        }
+    }
+
+    (renamed, replacements, lines)
+}
+
+// Rename an identifier across the project's source files, i.e. those with a
+// known comment syntax. With `dry_run` nothing is written.
+#[tauri::command]
+async fn rename_symbol(
+    root: String,
+    old: String,
+    new: String,
+    dry_run: bool,
+) -> Result<Vec<ReplaceResult>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    for symbol in [&old, &new] {
+        let valid = symbol.chars().all(is_identifier_char)
+            && symbol.chars().next().is_some_and(|c| !c.is_ascii_digit());
+        if !valid {
+            return Err(format!("Not a valid symbol name: {}", symbol));
+        }
+    }
+
+    if old == new {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    // Every rewrite is computed before anything is written, so a read problem
+    // can't leave the project half renamed
+    let mut rewrites = Vec::new();
+    for file in files {
+        let Some(prefixes) = line_comment_prefixes(language_for_path(&file)) else {
+            continue;
+        };
+        if is_binary_file(&file) {
+            continue;
+        }
+        // Files that aren't valid UTF-8 are left untouched rather than mangled
+        let Ok(contents) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let (renamed, replacements, lines) = rename_in_source(&contents, &old, &new, prefixes);
+        if replacements == 0 {
+            continue;
+        }
+
+        rewrites.push((file, renamed, replacements, lines));
+    }
+
+    // A failed write is reported on its file rather than aborting the rest
+    Ok(rewrites
+        .into_iter()
+        .map(|(file, renamed, replacements, lines)| {
+            let error = if dry_run {
+                None
+            } else {
+                write_atomic(&file, renamed.as_bytes()).err()
+            };
+
+            ReplaceResult {
+                path: file.to_string_lossy().to_string(),
+                replacements,
+                lines,
+                error,
+            }
+        })
+        .collect())
+}
+
+// Regexes matching a line that defines `symbol`, per language
+fn definition_patterns(path: &Path, symbol: &str) -> Vec<String> {
+    let sym = regex::escape(symbol);
+    let is_rust = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("rs"));
+
+    match language_for_path(path) {
+        // Labels with a colon, column-0 labels, and constant assignments
+        "asm" => vec![
+            format!(r"^\s*{sym}:"),
+            format!(r"^{sym}(?:\s|$)"),
+            format!(r"^\s*{sym}\s*(?:=|(?i:\.?equ|\.set)\b)"),
+        ],
+        "c" | "cpp" => vec![
+            format!(r"^\s*#\s*define\s+{sym}\b"),
+            format!(r"^\s*(?:typedef\s+)?(?:struct|enum|union|class)\s+{sym}\b"),
+            format!(r"^[A-Za-z_][\w\s\*&:<>]*\b{sym}\s*\([^;]*$"),
+        ],
+        "python" => vec![format!(r"^\s*(?:async\s+)?(?:def|class)\s+{sym}\b")],
+        "shell" => vec![format!(r"^\s*(?:function\s+)?{sym}\s*\(\)")],
+        _ if is_rust => vec![format!(
+            r"\b(?:fn|struct|enum|trait|type|const|static|mod|union)\s+{sym}\b|\bmacro_rules!\s*{sym}\b"
+        )],
+        _ => Vec::new(),
+    }
+}
+
+// First place `symbol` is defined, trying files in path order
+#[tauri::command]
+async fn find_definition(root: String, symbol: String) -> Result<Option<SearchMatch>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let symbol = symbol.trim();
+    if symbol.is_empty() {
+        return Ok(None);
+    }
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    // Patterns depend only on the language, so compile each set once
+    let mut compiled: HashMap<Vec<String>, Vec<regex::Regex>> = HashMap::new();
+    for file in files {
+        let patterns = definition_patterns(&file, symbol);
+        if patterns.is_empty() || is_binary_file(&file) {
+            continue;
+        }
+        let patterns = match compiled.entry(patterns) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let regexes = entry
+                    .key()
+                    .iter()
+                    .map(|pattern| regex::Regex::new(pattern).map_err(|e| e.to_string()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                entry.insert(regexes)
+            }
+        };
+        let Some(contents) = read_text_lossy(&file) else {
+            continue;
+        };
+
+        for (idx, line) in contents.lines().enumerate() {
+            if !patterns.iter().any(|pattern| pattern.is_match(line)) {
+                continue;
+            }
+
+            // Point at the symbol itself rather than the start of the line
+            let column = line
+                .match_indices(symbol)
+                .find(|(byte_idx, _)| is_whole_word(line, *byte_idx, byte_idx + symbol.len()))
+                .map_or(1, |(byte_idx, _)| line[..byte_idx].chars().count() + 1);
+
+            return Ok(Some(SearchMatch {
+                path: file.to_string_lossy().to_string(),
+                line: idx + 1,
+                column,
+                text: line.to_string(),
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuplicateLabel {
+    pub label: String,
+    pub locations: Vec<SearchMatch>,
+}
+
+// Global labels (`name:` at the start of a line) defined more than once across
+// the project's assembly files. Local labels like `@loop:` or `.loop:` are
+// meant to be reused, so they don't count.
+#[tauri::command]
+async fn find_duplicate_labels(root: String) -> Result<Vec<DuplicateLabel>, String> {
+    let root_path = Path::new(&root);
+    if !root_path.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    let label_pattern = regex::Regex::new(r"^([A-Za-z_]\w*):").map_err(|e| e.to_string())?;
+
+    let mut files = Vec::new();
+    collect_files(root_path, &mut files)?;
+    files.sort();
+
+    let mut definitions: BTreeMap<String, Vec<SearchMatch>> = BTreeMap::new();
+    for file in files {
+        if language_for_path(&file) != "asm" {
+            continue;
+        }
+        let Some(contents) = read_text_lossy(&file) else {
+            continue;
+        };
+
+        for (idx, line) in contents.lines().enumerate() {
+            if let Some(captures) = label_pattern.captures(line) {
+                definitions
+                    .entry(captures[1].to_string())
+                    .or_default()
+                    .push(SearchMatch {
+                        path: file.to_string_lossy().to_string(),
+                        line: idx + 1,
+                        column: 1,
+                        text: line.to_string(),
+                    });
+            }
+        }
+    }
+
+    Ok(definitions
+        .into_iter()
+        .filter(|(_, locations)| locations.len() > 1)
+        .map(|(label, locations)| DuplicateLabel { label, locations })
+        .collect())
+}
+
+fn record_startup_error(app: &tauri::AppHandle, message: String) {
+    app.state::<AppState>()
+        .startup_errors
+        .lock()
+        .unwrap()
+        .push(message);
+}
+
+// Errors from app setup, returned once so only the first window shows them
+#[tauri::command]
+fn take_startup_errors(state: State<AppState>) -> Vec<String> {
+    std::mem::take(&mut *state.startup_errors.lock().unwrap())
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(AppState {
+            projects: Mutex::new(HashMap::new()),
+            buffers: Mutex::new(HashMap::new()),
+            watchers: Mutex::new(HashMap::new()),
+            git_watchers: Mutex::new(HashMap::new()),
+            file_indexes: Mutex::new(HashMap::new()),
+            settings_watcher: Mutex::new(None),
+            syntax_set: Mutex::new(None),
+            highlight_cache: Mutex::new(HashMap::new()),
+            file_locks: Mutex::new(HashMap::new()),
+            tasks: Mutex::new(HashMap::new()),
+            command_timings: Mutex::new(HashMap::new()),
+            dir_cache: Mutex::new(HashMap::new()),
+            dir_reads: Mutex::new(HashMap::new()),
+            startup_errors: Mutex::new(Vec::new()),
+            next_window_id: AtomicUsize::new(1),
+            next_task_id: AtomicUsize::new(1),
+        })
+        .setup(|app| {
+            // Build and set the menu
+            let menu = build_app_menu(app.handle())?;
+            app.set_menu(menu)?;
+
+            // Restore the main window's last size and position
+            restore_main_window(app.handle());
+
+            // Keep windows in sync when the settings store changes
+            if let Err(e) = watch_settings(app.handle()) {
+                record_startup_error(app.handle(), format!("Failed to watch settings: {}", e));
+            }
+
+            // Keep the crash-recovery directory from growing without bound
+            if let Err(e) = prune_recovery_snapshots(app.handle()) {
+                record_startup_error(
+                    app.handle(),
+                    format!("Failed to prune recovery snapshots: {}", e),
+                );
+            }
+
+            if let Err(e) = prune_thumbnail_cache(app.handle()) {
+                record_startup_error(
+                    app.handle(),
+                    format!("Failed to prune thumbnail cache: {}", e),
+                );
+            }
+
+            Ok(())
         })
         .on_menu_event(|app, event| {
             let id = event.id().as_ref();
             match id {
                 "open_project" => {
-                    let _ = app.emit("menu-open-project", ());
+                    emit_to_focused(app, "menu-open-project");
                 }
                 "close_project" => {
-                    let _ = app.emit("menu-close-project", ());
+                    emit_to_focused(app, "menu-close-project");
                 }
                 "save_file" => {
-                    let _ = app.emit("menu-save-file", ());
+                    emit_to_focused(app, "menu-save-file");
                 }
                 "undo" => {
-                    let _ = app.emit("menu-undo", ());
+                    emit_to_focused(app, "menu-undo");
                 }
                 "redo" => {
-                    let _ = app.emit("menu-redo", ());
+                    emit_to_focused(app, "menu-redo");
                 }
                 "cut" => {
-                    let _ = app.emit("menu-cut", ());
+                    emit_to_focused(app, "menu-cut");
                 }
                 "copy" => {
-                    let _ = app.emit("menu-copy", ());
+                    emit_to_focused(app, "menu-copy");
                 }
                 "paste" => {
-                    let _ = app.emit("menu-paste", ());
+                    emit_to_focused(app, "menu-paste");
                 }
                 "select_all" => {
-                    let _ = app.emit("menu-select-all", ());
+                    emit_to_focused(app, "menu-select-all");
                 }
                 _ => {}
             }
         })
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { .. } if window.label() == MAIN_WINDOW => {
+                if let Some(window_state) = capture_window_state(window) {
+                    let _ = write_window_state(window.app_handle(), &window_state);
+                }
+            }
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) => {
+                handle_dropped_paths(window, paths);
+            }
+            tauri::WindowEvent::Destroyed => {
+                // Forget a window's project once the window is gone
+                let state = window.state::<AppState>();
+                state.projects.lock().unwrap().remove(window.label());
+                state.buffers.lock().unwrap().remove(window.label());
+                state.watchers.lock().unwrap().remove(window.label());
+                state.git_watchers.lock().unwrap().remove(window.label());
+                state
+                    .file_locks
+                    .lock()
+                    .unwrap()
+                    .retain(|_, owner| owner != window.label());
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             get_current_project,
             open_project_dialog,
@@ -356,8 +7277,320 @@ pub fn run() {
             read_directory,
             read_file_contents,
             write_file_contents,
-            read_file_binary
+            read_file_binary,
+            list_disk_templates,
+            create_project,
+            validate_filename,
+            rename_path,
+            read_file_with_encoding,
+            write_file_with_encoding,
+            toggle_line_comment,
+            reindent,
+            find_todos,
+            set_todo_markers,
+            open_project_in_new_window,
+            save_window_state,
+            load_window_state,
+            set_window_title,
+            set_active_file,
+            set_buffer_dirty,
+            update_title_from_state,
+            breadcrumb_for,
+            list_sibling_dirs,
+            move_path,
+            move_file_dialog,
+            common_ancestor,
+            export_tree_outline,
+            classify_files,
+            read_file_hex,
+            patch_file_bytes,
+            build_file_index,
+            invalidate_index,
+            search_in_files,
+            get_theme,
+            set_theme,
+            get_editor_font,
+            set_editor_font,
+            list_monospace_fonts,
+            rebuild_menu,
+            confirm_dialog,
+            show_message,
+            copy_path,
+            duplicate_file,
+            detect_language,
+            highlight_file,
+            load_syntax_definition,
+            read_file_with_bom,
+            get_formatter_config,
+            set_formatter_config,
+            format_file,
+            set_linter_pattern,
+            lint_file,
+            assemble,
+            get_emulator_path,
+            run_in_emulator,
+            load_project_manifest,
+            save_project_manifest,
+            save_tree_expansion,
+            load_tree_expansion,
+            close_buffer,
+            get_file_states,
+            open_external_url,
+            create_gitignore,
+            write_recovery_snapshot,
+            list_recovery_snapshots,
+            discard_recovery_snapshot,
+            detect_entry_points,
+            copy_path_to_clipboard,
+            list_all_open_files,
+            acquire_file_lock,
+            release_file_lock,
+            file_type_histogram,
+            run_task_to_file,
+            cancel_task,
+            git_repo_info,
+            get_ignored_paths,
+            git_blame,
+            git_diff_file,
+            git_discard_changes,
+            git_list_branches,
+            git_checkout_branch,
+            git_read_file_at_rev,
+            git_file_history,
+            get_command_timings,
+            read_directory_recursive,
+            read_directory_stream,
+            cancel_dir_read,
+            clear_directory_cache,
+            warm_index,
+            read_file_with_progress,
+            batch_rename,
+            detect_mixed_indentation,
+            normalize_indentation,
+            get_project_name,
+            set_project_display_name,
+            set_generated_markers,
+            is_generated_file,
+            open_app_data_dir,
+            reset_settings,
+            validate_project_structure,
+            merge_directories,
+            tree_checksum,
+            export_project_zip,
+            import_project_zip,
+            expand_path,
+            get_resource_stats,
+            list_mount_points,
+            resolve_symlink,
+            create_symlink,
+            get_thumbnail,
+            get_image_info,
+            convert_image,
+            extract_palette,
+            find_duplicate_files,
+            find_unreferenced_files,
+            get_default_line_ending,
+            set_default_line_ending,
+            find_project_root,
+            get_word_completions,
+            get_project_completions,
+            find_references,
+            rename_symbol,
+            find_definition,
+            find_duplicate_labels,
+            take_startup_errors
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh, empty directory under the system temp dir for one test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("retro-ide-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn case_only_rename_is_detected() {
+        assert!(is_case_only_rename(
+            Path::new("/project/Foo.asm"),
+            Path::new("/project/foo.asm")
+        ));
+        assert!(!is_case_only_rename(
+            Path::new("/project/foo.asm"),
+            Path::new("/project/foo.asm")
+        ));
+        assert!(!is_case_only_rename(
+            Path::new("/project/foo.asm"),
+            Path::new("/project/bar.asm")
+        ));
+    }
+
+    #[test]
+    fn rename_via_temp_changes_case() {
+        let dir = scratch_dir("rename-via-temp");
+        let from = dir.join("Foo.asm");
+        let to = dir.join("foo.asm");
+        fs::write(&from, "lda #1\n").unwrap();
+
+        rename_via_temp(&from, &to).unwrap();
+
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["foo.asm".to_string()]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "lda #1\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn case_only_rename_refuses_a_distinct_file() {
+        let dir = scratch_dir("case-only-collision");
+        let from = dir.join("File.txt");
+        let to = dir.join("file.txt");
+        fs::write(&from, "upper").unwrap();
+        fs::write(&to, "lower").unwrap();
+
+        // Only meaningful where both names can coexist
+        if fs::read_to_string(&from).unwrap() == "upper" {
+            assert!(!is_safe_case_only_rename(&from, &to));
+            assert_eq!(fs::read_to_string(&to).unwrap(), "lower");
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    const EXDEV: i32 = 18;
+
+    #[cfg(unix)]
+    #[test]
+    fn cross_device_rename_needs_copy_fallback() {
+        let e = std::io::Error::from_raw_os_error(EXDEV);
+        assert!(needs_copy_fallback(&e));
+
+        let e = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(!needs_copy_fallback(&e));
+    }
+
+    #[test]
+    fn copy_then_delete_removes_source_after_copy() {
+        let dir = scratch_dir("copy-then-delete");
+        let from = dir.join("sprite.bin");
+        let to = dir.join("moved.bin");
+        fs::write(&from, [1, 2, 3]).unwrap();
+
+        copy_then_delete(&from, &to, copy_entry).unwrap();
+
+        assert!(!from.exists());
+        assert_eq!(fs::read(&to).unwrap(), vec![1, 2, 3]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copy_then_delete_keeps_source_when_copy_fails() {
+        let dir = scratch_dir("copy-then-delete-fails");
+        let from = dir.join("sprite.bin");
+        let to = dir.join("moved.bin");
+        fs::write(&from, [1, 2, 3]).unwrap();
+
+        let result = copy_then_delete(&from, &to, |_, to| {
+            fs::write(to, [1]).unwrap();
+            Err("No space left on device".to_string())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&from).unwrap(), vec![1, 2, 3]);
+        assert!(!to.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rem_keyword_needs_a_word_boundary() {
+        let lines = vec!["10 REMAINING = 5".to_string()];
+        assert_eq!(
+            toggle_line_comment("basic".to_string(), lines).unwrap(),
+            vec!["10 REM REMAINING = 5".to_string()]
+        );
+
+        let lines = vec!["10 REM REMAINING = 5".to_string()];
+        assert_eq!(
+            toggle_line_comment("basic".to_string(), lines).unwrap(),
+            vec!["10 REMAINING = 5".to_string()]
+        );
+    }
+
+    #[test]
+    fn case_insensitive_search_columns_follow_the_original_line() {
+        // 'İ' grows by a byte when lowercased
+        let pattern = regex::RegexBuilder::new("FOO")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let mut results = Vec::new();
+        search_text(Path::new("a.asm"), "İİ foo", &pattern, &mut results);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].column, 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_mode_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir("write-atomic");
+        let script = dir.join("build.sh");
+        let link = dir.join("link.sh");
+        fs::write(&script, "echo old\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink(&script, &link).unwrap();
+
+        write_atomic(&link, b"echo new\n").unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&script).unwrap(), "echo new\n");
+        let mode = fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rename_in_source_skips_longer_identifiers_strings_and_comments() {
+        let source = "loop: lda #<loop ; back to loop\n jmp loop\n .byte \"loop\", loop_x, xloop\n";
+        let (renamed, replacements, lines) = rename_in_source(source, "loop", "main", &[";"]);
+
+        assert_eq!(
+            renamed,
+            "main: lda #<main ; back to loop\n jmp main\n .byte \"loop\", loop_x, xloop\n"
+        );
+        assert_eq!(replacements, 3);
+        assert_eq!(lines, vec![1, 2]);
+    }
+
+    #[test]
+    fn code_spans_stop_at_keyword_comments_only_on_word_boundaries() {
+        let line = "10 PRINT PREMIUM: REM PREMIUM";
+        let spans = code_spans(line, &["REM", "'"]);
+        assert_eq!(spans, vec![0..line.find("REM ").unwrap()]);
+
+        let (renamed, replacements, _) =
+            rename_in_source("10 X = 1 ' X\n", "X", "Y", &["REM", "'"]);
+        assert_eq!(renamed, "10 Y = 1 ' X\n");
+        assert_eq!(replacements, 1);
+    }
+}
@@ -2,7 +2,7 @@
 // This file initializes the application
 
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import {
   createEditor,
   getEditorContent,
@@ -22,6 +22,8 @@ import {
   initTabManager,
   openTab,
   setTabDirty,
+  setTabBom,
+  getTabByPath,
   renderTabs,
   closeAllTabs,
   Tab,
@@ -42,14 +44,32 @@ interface FileEntry {
   name: string;
   path: string;
   is_dir: boolean;
+  is_symlink: boolean;
   children?: FileEntry[];
 }
 
+interface DirectoryListing {
+  entries: FileEntry[];
+  truncated: boolean;
+  total: number;
+}
+
+// Maximum entries shown per folder before collapsing the rest into "…and N more"
+const DIRECTORY_ENTRY_LIMIT = 2000;
+
+// How long a notification stays up unless clicked away
+const NOTIFICATION_TIMEOUT_MS = 8000;
+
 interface FileData {
   data: string;
   mime_type: string;
 }
 
+interface TextFileContents {
+  contents: string;
+  has_bom: boolean;
+}
+
 // Editor types for different file kinds
 enum EditorType {
   Base = "base",
@@ -154,8 +174,7 @@ function updateProjectUI(project: ProjectState): void {
   if (fileTree) {
     fileTree.style.display = hasProject ? "block" : "none";
     if (hasProject && project.path) {
-      expandedFolders.clear();
-      loadFileTree(project.path);
+      restoreFileTree(project.path);
     } else {
       fileTree.innerHTML = "";
     }
@@ -170,13 +189,35 @@ function updateProjectUI(project: ProjectState): void {
   showWelcomeScreen();
 }
 
+async function restoreFileTree(path: string): Promise<void> {
+  try {
+    expandedFolders = new Set(await invoke<string[]>("load_tree_expansion"));
+  } catch (error) {
+    console.error("Failed to load tree expansion:", error);
+    expandedFolders = new Set();
+  }
+  await loadFileTree(path);
+
+  // Index in the background so the first search doesn't stall
+  invoke("warm_index").catch((error) => console.error("Failed to warm index:", error));
+}
+
+function saveTreeExpansion(): void {
+  invoke("save_tree_expansion", { expandedPaths: [...expandedFolders] }).catch((error) =>
+    console.error("Failed to save tree expansion:", error)
+  );
+}
+
 async function loadFileTree(path: string): Promise<void> {
   if (!fileTree) return;
 
   try {
-    const entries = await invoke<FileEntry[]>("read_directory", { path });
+    const listing = await invoke<DirectoryListing>("read_directory", {
+      path,
+      limit: DIRECTORY_ENTRY_LIMIT,
+    });
     fileTree.innerHTML = "";
-    const ul = createFileTreeElement(entries, 0);
+    const ul = createFileTreeElement(listing.entries, 0, listing.total - listing.entries.length);
     fileTree.appendChild(ul);
   } catch (error) {
     console.error("Failed to load file tree:", error);
@@ -184,7 +225,11 @@ async function loadFileTree(path: string): Promise<void> {
   }
 }
 
-function createFileTreeElement(entries: FileEntry[], depth: number): HTMLUListElement {
+function createFileTreeElement(
+  entries: FileEntry[],
+  depth: number,
+  hiddenCount = 0
+): HTMLUListElement {
   const ul = document.createElement("ul");
   ul.className = "file-tree-list";
   if (depth > 0) {
@@ -225,6 +270,10 @@ function createFileTreeElement(entries: FileEntry[], depth: number): HTMLUListEl
       if (isExpanded && entry.children) {
         const childUl = createFileTreeElement(entry.children, depth + 1);
         li.appendChild(childUl);
+      } else if (isExpanded) {
+        // Restored from a previous session; load the children lazily
+        expandedFolders.delete(entry.path);
+        void toggleFolder(entry, li, depth, false);
       }
     } else {
       const spacer = document.createElement("span");
@@ -249,6 +298,14 @@ function createFileTreeElement(entries: FileEntry[], depth: number): HTMLUListEl
     ul.appendChild(li);
   }
 
+  // Folders over the entry limit end with a placeholder for the rest
+  if (hiddenCount > 0) {
+    const li = document.createElement("li");
+    li.className = "file-tree-item file-tree-more";
+    li.textContent = `…and ${hiddenCount} more`;
+    ul.appendChild(li);
+  }
+
   return ul;
 }
 
@@ -273,7 +330,12 @@ function getFileIcon(filename: string): string {
   return iconMap[ext] ?? "📄";
 }
 
-async function toggleFolder(entry: FileEntry, li: HTMLLIElement, depth: number): Promise<void> {
+async function toggleFolder(
+  entry: FileEntry,
+  li: HTMLLIElement,
+  depth: number,
+  persist = true
+): Promise<void> {
   const isExpanded = expandedFolders.has(entry.path);
 
   if (isExpanded) {
@@ -296,10 +358,17 @@ async function toggleFolder(entry: FileEntry, li: HTMLLIElement, depth: number):
     expandedFolders.add(entry.path);
 
     try {
-      const children = await invoke<FileEntry[]>("read_directory", { path: entry.path });
-      entry.children = children;
+      const listing = await invoke<DirectoryListing>("read_directory", {
+        path: entry.path,
+        limit: DIRECTORY_ENTRY_LIMIT,
+      });
+      entry.children = listing.entries;
 
-      const childUl = createFileTreeElement(children, depth + 1);
+      const childUl = createFileTreeElement(
+        listing.entries,
+        depth + 1,
+        listing.total - listing.entries.length
+      );
       li.appendChild(childUl);
 
       const chevron = li.querySelector(".file-tree-chevron");
@@ -314,6 +383,10 @@ async function toggleFolder(entry: FileEntry, li: HTMLLIElement, depth: number):
       console.error("Failed to load folder contents:", error);
     }
   }
+
+  if (persist) {
+    saveTreeExpansion();
+  }
 }
 
 async function openFile(entry: FileEntry): Promise<void> {
@@ -386,6 +459,24 @@ function openBaseEditor(entry: FileEntry): void {
   `;
 }
 
+// Non-blocking message for failures that don't belong to a single editor
+function showNotification(message: string): void {
+  const notification = document.createElement("div");
+  notification.className = "notification error";
+  notification.textContent = message;
+  notification.addEventListener("click", () => notification.remove());
+
+  let container = document.getElementById("notifications");
+  if (!container) {
+    container = document.createElement("div");
+    container.id = "notifications";
+    document.body.appendChild(container);
+  }
+  container.appendChild(notification);
+
+  setTimeout(() => notification.remove(), NOTIFICATION_TIMEOUT_MS);
+}
+
 // Text Editor - CodeMirror integration
 async function openTextEditor(entry: FileEntry): Promise<void> {
   if (!editorArea) return;
@@ -397,7 +488,9 @@ async function openTextEditor(entry: FileEntry): Promise<void> {
   if (cached) {
     content = cached.content;
   } else {
-    content = await invoke<string>("read_file_contents", { path: entry.path });
+    const file = await invoke<TextFileContents>("read_file_with_bom", { path: entry.path });
+    content = file.contents;
+    setTabBom(entry.path, file.has_bom);
   }
   
   const language = detectLanguage(entry.name);
@@ -448,7 +541,11 @@ async function saveCurrentFile(filePath?: string): Promise<void> {
   if (content === null) return;
 
   try {
-    await invoke("write_file_contents", { path, contents: content });
+    await invoke("write_file_contents", {
+      path,
+      contents: content,
+      writeBom: getTabByPath(path)?.hasBom ?? false,
+    });
     markEditorClean();
     
     // Update tab dirty state and re-render
@@ -550,6 +647,7 @@ async function handleTabChange(tab: Tab | null): Promise<void> {
     name: tab.filename,
     path: tab.filePath,
     is_dir: false,
+    is_symlink: false,
   };
 
   try {
@@ -580,6 +678,9 @@ function handleTabClose(tab: Tab): boolean {
   
   // Remove from cache
   editorContentCache.delete(tab.filePath);
+  invoke("close_buffer", { path: tab.filePath }).catch((error) =>
+    console.error("Failed to close buffer:", error)
+  );
   
   // If this was the current editor, destroy it
   const editor = getActiveEditor();
@@ -635,43 +736,67 @@ function setupEventListeners(): void {
 }
 
 async function setupMenuListeners(): Promise<void> {
+  // Menu events are sent to the focused window only
+  const appWindow = getCurrentWebviewWindow();
+
   // File menu events
-  await listen("menu-open-project", () => {
+  await appWindow.listen("menu-open-project", () => {
     openProject();
   });
 
-  await listen("menu-close-project", () => {
+  await appWindow.listen("menu-close-project", () => {
     closeProject();
   });
 
-  await listen("menu-save-file", () => {
+  await appWindow.listen("menu-save-file", () => {
     saveCurrentFile();
   });
 
   // Edit menu events
-  await listen("menu-undo", () => {
+  await appWindow.listen("menu-undo", () => {
     editorUndo();
   });
 
-  await listen("menu-redo", () => {
+  await appWindow.listen("menu-redo", () => {
     editorRedo();
   });
 
-  await listen("menu-cut", () => {
+  await appWindow.listen("menu-cut", () => {
     editorCut();
   });
 
-  await listen("menu-copy", () => {
+  await appWindow.listen("menu-copy", () => {
     editorCopy();
   });
 
-  await listen("menu-paste", () => {
+  await appWindow.listen("menu-paste", () => {
     editorPaste();
   });
 
-  await listen("menu-select-all", () => {
+  await appWindow.listen("menu-select-all", () => {
     editorSelectAll();
   });
+
+  // Failures in background work on the backend
+  await appWindow.listen<{ message: string }>("backend-error", (event) => {
+    console.error(event.payload.message);
+    showNotification(event.payload.message);
+  });
+
+  await appWindow.listen<{ root: string; message: string }>("index-failed", (event) => {
+    showNotification(`Failed to index ${event.payload.root}: ${event.payload.message}`);
+  });
+
+  // Drag-and-drop is handled by the backend, which reports what to open
+  await appWindow.listen<ProjectState>("project-opened", (event) => {
+    updateProjectUI(event.payload);
+  });
+
+  await appWindow.listen<FileEntry[]>("files-dropped", async (event) => {
+    for (const entry of event.payload) {
+      await openFile(entry);
+    }
+  });
 }
 
 document.addEventListener("DOMContentLoaded", async () => {
@@ -712,6 +837,10 @@ document.addEventListener("DOMContentLoaded", async () => {
   setupEventListeners();
   await setupMenuListeners();
 
+  invoke<string[]>("take_startup_errors")
+    .then((errors) => errors.forEach(showNotification))
+    .catch((error) => console.error("Failed to read startup errors:", error));
+
   // Try to load the last opened project
   await loadLastProject();
 });
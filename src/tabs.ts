@@ -6,6 +6,8 @@ export interface Tab {
   filePath: string;
   filename: string;
   isDirty: boolean;
+  // Whether the file on disk starts with a UTF-8 BOM, kept on save
+  hasBom: boolean;
   type: "text" | "image" | "base";
 }
 
@@ -59,6 +61,7 @@ export function openTab(filePath: string, filename: string, type: "text" | "imag
     filePath,
     filename,
     isDirty: false,
+    hasBom: false,
     type,
   };
   
@@ -149,6 +152,14 @@ export function setTabDirty(filePath: string, isDirty: boolean): void {
   }
 }
 
+// Record whether a tab's file carries a UTF-8 BOM
+export function setTabBom(filePath: string, hasBom: boolean): void {
+  const tab = getTabByPath(filePath);
+  if (tab) {
+    tab.hasBom = hasBom;
+  }
+}
+
 // Check if any tab is dirty
 export function hasUnsavedTabs(): boolean {
   return tabManager.tabs.some(t => t.isDirty);